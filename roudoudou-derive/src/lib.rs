@@ -0,0 +1,286 @@
+//! `#[derive(OdooModel)]` for roudoudou
+//!
+//! ```ignore
+//! #[derive(OdooModel)]
+//! #[odoo(model = "stock.label")]
+//! struct Label {
+//!     id: u32,
+//!     name: String,
+//!     #[odoo(rename = "is_terminal")]
+//!     terminal: bool,
+//!     product_id: Option<Many2one<Product>>,
+//!     #[odoo(readonly)]
+//!     display_name: String,
+//!     #[odoo(skip)]
+//!     printed: bool,
+//! }
+//! ```
+//!
+//! A field named `id` holds the record id, it is never written, nor are
+//! `readonly` fields.
+//!
+//! `#[derive(OdooSelection)]` maps a fieldless enum to the keys of a
+//! selection field, each variant takes a `key` (snake cased name by
+//! default) and a `label` (its name by default).
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Ident, Lit, Meta,
+    MetaNameValue, NestedMeta,
+};
+
+#[proc_macro_derive(OdooModel, attributes(odoo))]
+pub fn derive_odoo_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(OdooSelection, attributes(odoo))]
+pub fn derive_odoo_selection(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_selection(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// items of every `#[odoo(...)]` attribute
+fn odoo_attrs(attrs: &[Attribute]) -> syn::Result<Vec<Meta>> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("odoo")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(meta) => metas.push(meta),
+                        other => return Err(Error::new_spanned(other, "expected an odoo option")),
+                    }
+                }
+            }
+            other => return Err(Error::new_spanned(other, "expected #[odoo(...)]")),
+        }
+    }
+    Ok(metas)
+}
+
+fn string_value(meta: &MetaNameValue) -> syn::Result<String> {
+    match &meta.lit {
+        Lit::Str(s) => Ok(s.value()),
+        other => Err(Error::new_spanned(other, "expected a string")),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let mut model = None;
+    for meta in odoo_attrs(&input.attrs)? {
+        match meta {
+            Meta::NameValue(meta) if meta.path.is_ident("model") => {
+                model = Some(string_value(&meta)?)
+            }
+            other => return Err(Error::new_spanned(other, "expected `model = \"...\"`")),
+        }
+    }
+    let model = model.ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "OdooModel needs #[odoo(model = \"...\")]",
+        )
+    })?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    ident,
+                    "OdooModel needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                ident,
+                "OdooModel can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut specs = Vec::new();
+    let mut reads = Vec::new();
+    let mut writes = Vec::new();
+    let mut id = quote!(::std::option::Option::None);
+    for field in fields {
+        let member = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let mut name = member.unraw().to_string();
+        let mut skip = false;
+        let mut readonly = false;
+        for meta in odoo_attrs(&field.attrs)? {
+            match meta {
+                Meta::NameValue(meta) if meta.path.is_ident("rename") => {
+                    name = string_value(&meta)?
+                }
+                Meta::Path(path) if path.is_ident("skip") => skip = true,
+                Meta::Path(path) if path.is_ident("readonly") => readonly = true,
+                other => {
+                    return Err(Error::new_spanned(
+                        other,
+                        "expected `rename = \"...\"`, `readonly` or `skip`",
+                    ))
+                }
+            }
+        }
+        if skip {
+            reads.push(quote!(#member: ::std::default::Default::default()));
+        } else if name == "id" {
+            reads.push(quote!(#member: record.id()));
+            id = quote!(::std::option::Option::Some(self.#member));
+        } else {
+            specs.push(quote! {
+                ::roudoudou::typed::FieldSpec {
+                    name: #name,
+                    kinds: <#ty as ::roudoudou::typed::FieldValue>::kinds(),
+                    relation: <#ty as ::roudoudou::typed::FieldValue>::relation(),
+                    keys: <#ty as ::roudoudou::typed::FieldValue>::keys(),
+                }
+            });
+            reads.push(quote!(#member: ::roudoudou::typed::read_field(record, #name)?));
+            if readonly {
+                continue;
+            }
+            writes.push(quote! {
+                values.insert(
+                    #name.to_owned(),
+                    ::roudoudou::typed::FieldValue::to_odoo(&self.#member),
+                );
+            });
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::roudoudou::typed::OdooModel for #ident #ty_generics #where_clause {
+            const MODEL: &'static str = #model;
+
+            fn fields() -> ::std::vec::Vec<::roudoudou::typed::FieldSpec> {
+                ::std::vec![#(#specs),*]
+            }
+            fn id(&self) -> ::std::option::Option<u32> {
+                #id
+            }
+            fn from_record(
+                record: &::roudoudou::Record<'_, '_>,
+            ) -> ::roudoudou::Result<Self> {
+                ::std::result::Result::Ok(#ident { #(#reads),* })
+            }
+            fn to_values(&self) -> ::roudoudou::typed::Values {
+                let mut values = ::roudoudou::typed::Values::new();
+                #(#writes)*
+                values
+            }
+        }
+    })
+}
+
+/// `InUse` -> `in_use`
+fn snake_case(ident: &Ident) -> String {
+    let mut snake = String::new();
+    for (i, c) in ident.unraw().to_string().chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn expand_selection(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let variants = match &input.data {
+        Data::Enum(data) if !data.variants.is_empty() => &data.variants,
+        _ => {
+            return Err(Error::new_spanned(
+                ident,
+                "OdooSelection can only be derived for enums with variants",
+            ))
+        }
+    };
+    let mut values = Vec::new();
+    let mut keys = Vec::new();
+    let mut labels = Vec::new();
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "OdooSelection variants can not have fields",
+            ));
+        }
+        let name = &variant.ident;
+        let mut key = snake_case(name);
+        let mut label = name.unraw().to_string();
+        for meta in odoo_attrs(&variant.attrs)? {
+            match meta {
+                Meta::NameValue(meta) if meta.path.is_ident("key") => key = string_value(&meta)?,
+                Meta::NameValue(meta) if meta.path.is_ident("label") => {
+                    label = string_value(&meta)?
+                }
+                other => {
+                    return Err(Error::new_spanned(
+                        other,
+                        "expected `key = \"...\"` or `label = \"...\"`",
+                    ))
+                }
+            }
+        }
+        values.push(quote!(#ident::#name));
+        keys.push(quote!(#ident::#name => #key));
+        labels.push(quote!(#ident::#name => #label));
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::roudoudou::Selection for #ident #ty_generics #where_clause {
+            const VALUES: &'static [Self] = &[#(#values),*];
+
+            fn key(&self) -> &'static str {
+                match self {
+                    #(#keys,)*
+                }
+            }
+            fn label(&self) -> &'static str {
+                match self {
+                    #(#labels,)*
+                }
+            }
+        }
+
+        impl #impl_generics ::roudoudou::typed::FieldValue for #ident #ty_generics #where_clause {
+            fn kinds() -> &'static [&'static str] {
+                &["selection"]
+            }
+            fn keys() -> ::std::vec::Vec<&'static str> {
+                <Self as ::roudoudou::Selection>::VALUES
+                    .iter()
+                    .map(::roudoudou::Selection::key)
+                    .collect()
+            }
+            fn from_odoo(value: ::roudoudou::OdooValue) -> ::std::option::Option<Self> {
+                value
+                    .as_str()
+                    .and_then(<Self as ::roudoudou::Selection>::from_key)
+            }
+            fn to_odoo(&self) -> ::roudoudou::serde_json::Value {
+                ::roudoudou::serde_json::Value::from(::roudoudou::Selection::key(self))
+            }
+        }
+    })
+}
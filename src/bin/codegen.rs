@@ -0,0 +1,36 @@
+//! generate Rust models from a database
+//!
+//! ```text
+//! cargo run --bin codegen -- ota8 admin admin stock.label product.product > src/models.rs
+//! ```
+use dotenv::dotenv;
+use roudoudou::OdooClient;
+use std::env;
+use std::process;
+
+fn main() {
+    dotenv().ok();
+    env_logger::init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 4 {
+        eprintln!("usage: codegen <db> <login> <password> <model>...");
+        process::exit(2);
+    }
+    let mut cli = OdooClient::new();
+    let cli = match cli.login(&args[0], &args[1], &args[2]) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("could not login to {}: {}", args[0], err);
+            process::exit(1);
+        }
+    };
+    let models: Vec<&str> = args[3..].iter().map(|name| name.as_str()).collect();
+    match cli.generate_source(&models) {
+        Ok(source) => print!("{}", source),
+        Err(err) => {
+            eprintln!("could not generate models: {}", err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! binary fields and attachments
+//!
+//! Records are read with `bin_size`: binary fields hold their human readable
+//! size (`"12.50 Kb"`), never their content. Contents are fetched on demand,
+//! small ones for the whole recordset in one `read`, large ones from
+//! `/web/content` so they are not buffered as base64.
+//!
+//! ```ignore
+//! let mut file = File::create("firmware.apk")?;
+//! attachment.record(0).unwrap().download("datas", &mut file)?;
+//! attachment.upload("datas", File::open("firmware-2.apk")?)?;
+//! ```
+use base64::write::EncoderStringWriter;
+use serde_json::{json, Value};
+use std::io::{self, Read, Write};
+
+use crate::{Error, ErrorKind, OdooRpc, Record, RecordSet, Result, ResultExt};
+
+const ODOO_CONTENT: &str = "/web/content";
+
+/// contents bigger than this are downloaded from `/web/content`
+const INLINE_BINARY_LIMIT: u64 = 1 << 20;
+
+/// bytes of a size formatted by the server (`"12.50 Kb"`, `"3 bytes"`)
+pub fn parse_size(size: &str) -> Option<u64> {
+    let mut parts = size.split_whitespace();
+    let value: f64 = parts.next()?.parse().ok()?;
+    let unit = match parts.next() {
+        None | Some("bytes") | Some("b") => 1u64,
+        Some("Kb") | Some("KB") => 1 << 10,
+        Some("Mb") | Some("MB") => 1 << 20,
+        Some("Gb") | Some("GB") => 1 << 30,
+        Some("Tb") | Some("TB") => 1 << 40,
+        Some(_) => return None,
+    };
+    Some((value * unit as f64) as u64)
+}
+
+impl OdooRpc {
+    /// stream the content of field `field` of record `model,id` from `/web/content`
+    fn download(&self, model: &str, id: u32, field: &str, writer: &mut dyn Write) -> Result<u64> {
+        let endpoint = self.base_url.join(ODOO_CONTENT).unwrap();
+        let id = id.to_string();
+        let mut resp = self
+            .http
+            .get(endpoint.as_str())
+            .query(&[
+                ("model", model),
+                ("id", id.as_str()),
+                ("field", field),
+                ("download", "true"),
+            ])
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .chain_err(|| format!("could not download {},{} {}", model, id, field))?;
+        io::copy(&mut resp, writer)
+            .chain_err(|| format!("could not save {},{} {}", model, id, field))
+    }
+}
+
+impl Record<'_, '_> {
+    /// size in bytes of binary field `name`, `None` if empty
+    pub fn binary_size(&self, name: &str) -> Option<u64> {
+        self.get(name)
+            .and_then(|size| size.as_str().and_then(parse_size))
+    }
+    /// content of binary field `name`, `None` if empty
+    pub fn binary(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let size = match self.binary_size(name) {
+            None => return Ok(None),
+            Some(size) => size,
+        };
+        let mut content = Vec::with_capacity(size as usize);
+        if size > INLINE_BINARY_LIMIT {
+            self.download(name, &mut content)?;
+            return Ok(Some(content));
+        }
+        let set = self.recordset();
+        set.prefetch_contents(name)?;
+        let contents = set.contents.borrow();
+        match contents.get(&self.id()).and_then(|row| row.get(name)) {
+            Some(Value::String(encoded)) => {
+                content = base64::decode(encoded.replace('\n', ""))
+                    .chain_err(|| format!("invalid base64 in {}.{}", set.model.name(), name))?;
+                Ok(Some(content))
+            }
+            _ => Ok(None),
+        }
+    }
+    /// stream the content of binary field `name` to `writer`, returns its size
+    pub fn download(&self, name: &str, writer: &mut dyn Write) -> Result<u64> {
+        let model = &self.recordset().model;
+        model
+            .cli
+            .api
+            .rpc
+            .download(model.name(), self.id(), name, writer)
+    }
+}
+
+impl RecordSet<'_> {
+    /// read the small contents of binary field `name` not loaded yet, for
+    /// every record of this set at once
+    fn prefetch_contents(&self, name: &str) -> Result<()> {
+        let missing: Vec<u32> = {
+            let contents = self.contents.borrow();
+            let loaded = |id: u32| matches!(contents.get(&id), Some(row) if row.contains_key(name));
+            let small =
+                |size: Option<u64>| matches!(size, Some(size) if size <= INLINE_BINARY_LIMIT);
+            self.iter()
+                .filter(|rec| !loaded(rec.id()) && small(rec.binary_size(name)))
+                .map(|rec| rec.id())
+                .collect()
+        };
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let rows = self
+            .model
+            .execute("read", vec![json!(missing), json!([name])])?;
+        let mut contents = self.contents.borrow_mut();
+        for row in rows.as_array().into_iter().flatten() {
+            if let (Some(id), Some(content)) =
+                (row.get("id").and_then(|id| id.as_u64()), row.get(name))
+            {
+                contents
+                    .entry(id as u32)
+                    .or_default()
+                    .insert(name.to_owned(), content.to_owned());
+            }
+        }
+        Ok(())
+    }
+    /// write the content of `reader` to binary field `name` of every record
+    pub fn upload<R: Read>(&self, name: &str, mut reader: R) -> Result<bool> {
+        match self.model.desc.fields.get(name) {
+            Some(field) if field.type_ == "binary" => {}
+            _ => {
+                return Err(Error::from_kind(ErrorKind::InvalidField(format!(
+                    "{}.{} is not a binary field",
+                    self.model.name(),
+                    name
+                ))))
+            }
+        }
+        let mut encoder = EncoderStringWriter::new(base64::STANDARD);
+        io::copy(&mut reader, &mut encoder).chain_err(|| "could not read upload")?;
+        self.write(json!({ name: encoder.into_inner() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_size;
+    use crate::tests::{offline_client, offline_model, offline_records};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("3 bytes"), Some(3));
+        assert_eq!(parse_size("12.50 Kb"), Some(12800));
+        assert_eq!(parse_size("2.00 Mb"), Some(2 << 20));
+        assert_eq!(parse_size("1024"), Some(1024));
+        assert_eq!(parse_size("iVBORw0KGgo="), None);
+    }
+
+    #[test]
+    fn test_binary_contents() {
+        let cli = offline_client();
+        let model = offline_model(&cli, "ir.attachment", &[("datas", "binary")]);
+        let rows = json!([
+            {"id": 1, "datas": "3 bytes"},
+            {"id": 2, "datas": false},
+            {"id": 3, "datas": "2 bytes"}
+        ]);
+        // small contents are missing, the offline client can not read them
+        let attachments = offline_records(&model, rows.clone());
+        assert!(attachments.record(2).unwrap().binary("datas").is_err());
+
+        // as loaded by one `read` for the whole set
+        let attachments = offline_records(&model, rows);
+        for (id, content) in &[(1, "Zm9v"), (3, "aGk=")] {
+            attachments.contents.borrow_mut().insert(
+                *id,
+                json!({ "datas": content }).as_object().unwrap().clone(),
+            );
+        }
+        let binary = |i: usize| attachments.record(i).unwrap().binary("datas").unwrap();
+        assert_eq!(binary(0), Some(b"foo".to_vec()));
+        assert_eq!(binary(1), None);
+        assert_eq!(binary(2), Some(b"hi".to_vec()));
+    }
+}
@@ -0,0 +1,260 @@
+//! client-side record cache
+//!
+//! Values read from the server are kept by `(model, id, field)` until the
+//! records are written, unlinked or have a method called on them.
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// how long cached values are kept
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CachePolicy {
+    /// every read hits the server
+    Disabled,
+    /// values are kept until invalidated
+    #[default]
+    Session,
+    /// values are kept until invalidated, at most for the given duration
+    Ttl(Duration),
+}
+
+/// cache usage counters
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CacheMetrics {
+    /// values served from the cache
+    pub hits: u64,
+    /// values that had to be read from the server
+    pub misses: u64,
+    /// values dropped by invalidation
+    pub invalidations: u64,
+}
+
+type CacheKey = (String, u32, String);
+
+/// model methods known not to change records, calling them keeps the cache
+const READ_ONLY_METHODS: &[&str] = &[
+    "check_access_rights",
+    "default_get",
+    "fields_get",
+    "formatted_read_group",
+    "name_get",
+    "name_search",
+    "onchange",
+    "read",
+    "read_group",
+    "search",
+    "search_count",
+    "search_read",
+    "web_read",
+    "web_read_group",
+    "web_search_read",
+];
+
+/// can calling `method` leave cached records stale?
+pub(crate) fn invalidates(method: &str) -> bool {
+    !READ_ONLY_METHODS.contains(&method)
+}
+
+/// record cache keyed by `(model, id, field)`
+#[derive(Debug, Default)]
+pub struct RecordCache {
+    policy: CachePolicy,
+    entries: BTreeMap<CacheKey, (Value, Instant)>,
+    metrics: CacheMetrics,
+}
+
+impl RecordCache {
+    pub fn new(policy: CachePolicy) -> Self {
+        RecordCache {
+            policy,
+            ..Default::default()
+        }
+    }
+    pub fn policy(&self) -> CachePolicy {
+        self.policy
+    }
+    /// change the policy, switching to `Disabled` empties the cache
+    pub fn set_policy(&mut self, policy: CachePolicy) {
+        self.policy = policy;
+        if policy == CachePolicy::Disabled {
+            self.entries.clear();
+        }
+    }
+    pub fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    /// split `ids` into rows fully served from the cache and ids to read
+    ///
+    /// Empty `names` stand for all the fields, which the cache can not know
+    /// of: every id is read.
+    pub fn lookup(&mut self, model: &str, ids: &[u32], names: &[&str]) -> (Vec<Value>, Vec<u32>) {
+        let mut cached = Vec::new();
+        let mut missing = Vec::new();
+        if names.is_empty() {
+            self.metrics.misses += ids.len() as u64;
+            return (cached, ids.to_vec());
+        }
+        if self.policy == CachePolicy::Disabled {
+            self.metrics.misses += (ids.len() * names.len()) as u64;
+            return (cached, ids.to_vec());
+        }
+        for id in ids {
+            let mut row = Map::new();
+            row.insert("id".to_owned(), Value::from(*id));
+            for name in names {
+                match self.get(model, *id, name) {
+                    Some(value) => {
+                        self.metrics.hits += 1;
+                        row.insert(name.to_string(), value);
+                    }
+                    None => self.metrics.misses += 1,
+                }
+            }
+            if names.iter().all(|name| row.contains_key(*name)) {
+                cached.push(Value::Object(row));
+            } else {
+                missing.push(*id);
+            }
+        }
+        (cached, missing)
+    }
+
+    fn get(&mut self, model: &str, id: u32, name: &str) -> Option<Value> {
+        if name == "id" {
+            return Some(Value::from(id));
+        }
+        let key = (model.to_owned(), id, name.to_owned());
+        let expired = match (self.entries.get(&key), self.policy) {
+            (None, _) => return None,
+            (Some((_, stored)), CachePolicy::Ttl(ttl)) => stored.elapsed() > ttl,
+            (Some(_), _) => false,
+        };
+        if expired {
+            self.entries.remove(&key);
+            return None;
+        }
+        self.entries.get(&key).map(|(value, _)| value.clone())
+    }
+
+    /// store rows read from the server (each with an `id`)
+    pub fn store(&mut self, model: &str, rows: &[Value]) {
+        if self.policy == CachePolicy::Disabled {
+            return;
+        }
+        let now = Instant::now();
+        for row in rows {
+            let id = match row.get("id").and_then(|id| id.as_u64()) {
+                Some(id) => id as u32,
+                None => continue,
+            };
+            if let Value::Object(row) = row {
+                for (name, value) in row.iter().filter(|(name, _)| *name != "id") {
+                    self.entries.insert(
+                        (model.to_owned(), id, name.to_owned()),
+                        (value.clone(), now),
+                    );
+                }
+            }
+        }
+    }
+
+    /// drop cached values of `model`, restricted to `names` and `ids` if given
+    pub fn invalidate(&mut self, model: &str, names: Option<&[&str]>, ids: Option<&[u32]>) {
+        let before = self.entries.len();
+        self.entries.retain(|(m, id, name), _| {
+            let named = match names {
+                Some(names) => names.contains(&name.as_str()),
+                None => true,
+            };
+            let listed = match ids {
+                Some(ids) => ids.contains(id),
+                None => true,
+            };
+            m != model || !named || !listed
+        });
+        self.metrics.invalidations += (before - self.entries.len()) as u64;
+    }
+
+    /// drop every cached value
+    pub fn clear(&mut self) {
+        self.metrics.invalidations += self.entries.len() as u64;
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{invalidates, CachePolicy, RecordCache};
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn test_invalidates() {
+        assert!(!invalidates("read"));
+        assert!(!invalidates("search_count"));
+        assert!(invalidates("write"));
+        assert!(invalidates("action_confirm"));
+    }
+
+    #[test]
+    fn test_cache_lookup() {
+        let mut cache = RecordCache::new(CachePolicy::Session);
+        cache.store(
+            "stock.label",
+            &[json!({"id": 1, "name": "A", "state": "draft"})],
+        );
+
+        let (cached, missing) = cache.lookup("stock.label", &[1, 2], &["name", "state"]);
+        assert_eq!(
+            cached,
+            vec![json!({"id": 1, "name": "A", "state": "draft"})]
+        );
+        assert_eq!(missing, vec![2]);
+        assert_eq!(cache.metrics().hits, 2);
+        assert_eq!(cache.metrics().misses, 2);
+
+        let (cached, missing) = cache.lookup("stock.label", &[1], &["name", "product_id"]);
+        assert!(cached.is_empty());
+        assert_eq!(missing, vec![1]);
+
+        // no names means all fields
+        let (cached, missing) = cache.lookup("stock.label", &[1], &[]);
+        assert!(cached.is_empty());
+        assert_eq!(missing, vec![1]);
+        assert_eq!(cache.metrics().hits, 3);
+    }
+
+    #[test]
+    fn test_cache_invalidate() {
+        let mut cache = RecordCache::new(CachePolicy::Session);
+        cache.store(
+            "stock.label",
+            &[json!({"id": 1, "name": "A"}), json!({"id": 2, "name": "B"})],
+        );
+        cache.store("res.users", &[json!({"id": 1, "name": "Administrator"})]);
+
+        cache.invalidate("stock.label", None, Some(&[1]));
+        assert_eq!(cache.lookup("stock.label", &[1, 2], &["name"]).1, vec![1]);
+        assert_eq!(
+            cache.lookup("res.users", &[1], &["name"]).1,
+            Vec::<u32>::new()
+        );
+        assert_eq!(cache.metrics().invalidations, 1);
+
+        cache.clear();
+        assert_eq!(cache.lookup("res.users", &[1], &["name"]).1, vec![1]);
+    }
+
+    #[test]
+    fn test_cache_policies() {
+        let mut cache = RecordCache::new(CachePolicy::Disabled);
+        cache.store("stock.label", &[json!({"id": 1, "name": "A"})]);
+        assert_eq!(cache.lookup("stock.label", &[1], &["name"]).1, vec![1]);
+
+        let mut cache = RecordCache::new(CachePolicy::Ttl(Duration::from_secs(0)));
+        cache.store("stock.label", &[json!({"id": 1, "name": "A"})]);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.lookup("stock.label", &[1], &["name"]).1, vec![1]);
+    }
+}
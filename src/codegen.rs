@@ -0,0 +1,393 @@
+//! Rust source generation from server metadata
+//!
+//! Models are written as `#[derive(OdooModel)]` structs, with an enum per
+//! selection field and a wrapper per public method. Output is sorted by
+//! name so that regenerating against another database gives a reviewable
+//! diff.
+//!
+//! ```ignore
+//! let source = cli.generate_source(&["stock.label", "product.product"])?;
+//! std::fs::write("src/models.rs", source)?;
+//! ```
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::{FieldDescriptor, Method, MethodKind, ObjectDescriptor, OdooClient, Result};
+
+const HEADER: &str = "// @generated by roudoudou codegen, do not edit
+#![allow(dead_code, unused_imports, clippy::all)]
+
+use roudoudou::chrono::{DateTime, NaiveDate, Utc};
+use roudoudou::serde_json::Value;
+use roudoudou::{
+    Args, Many2one, OdooClient, OdooModel, OdooSelection, OdooValue, Result, X2many,
+};
+";
+
+/// keywords which need a raw identifier
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+/// methods of the `OdooModel` trait, wrappers with these names get a `_` suffix
+const TRAIT_METHODS: &[&str] = &[
+    "check",
+    "field_names",
+    "fields",
+    "from_record",
+    "id",
+    "to_values",
+];
+
+/// `stock.label` -> `StockLabel`, `0` -> `V0`
+fn camel_case(name: &str) -> String {
+    let mut camel = String::new();
+    for part in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            camel.push(first.to_ascii_uppercase());
+            camel.extend(chars);
+        }
+    }
+    match camel.chars().next() {
+        None => "Empty".to_owned(),
+        Some(first) if first.is_ascii_digit() => format!("V{}", camel),
+        Some(_) => camel,
+    }
+}
+
+/// identifier of the wrapper of method `name`
+fn method_name(name: &str) -> String {
+    if TRAIT_METHODS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        snake_case(name)
+    }
+}
+
+/// snake case identifier for field and method `name`
+fn snake_case(name: &str) -> String {
+    match name {
+        "self" | "Self" | "crate" | "super" => format!("{}_", name),
+        name if KEYWORDS.contains(&name) => format!("r#{}", name),
+        name => name.to_owned(),
+    }
+}
+
+/// one line doc comment, from a field label or a selection value
+fn doc(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// name of the enum generated for selection field `name` of `model`
+fn selection_name(model: &str, name: &str) -> String {
+    format!("{}{}", camel_case(model), camel_case(name))
+}
+
+/// Rust type of `field`, `None` for fields not mapped (binary)
+fn field_type(
+    model: &str,
+    name: &str,
+    field: &FieldDescriptor,
+    known: &BTreeSet<&str>,
+) -> Option<String> {
+    let comodel = field
+        .relation
+        .as_deref()
+        .filter(|relation| known.contains(relation))
+        .map(camel_case);
+    let (type_, nullable) = match (field.type_.as_str(), comodel) {
+        ("boolean", _) => ("bool".to_owned(), false),
+        ("integer", _) => ("i64".to_owned(), false),
+        ("float", _) | ("monetary", _) => ("f64".to_owned(), false),
+        ("char", _) | ("text", _) | ("html", _) => ("String".to_owned(), true),
+        ("selection", _) if !field.selection.is_empty() => (selection_name(model, name), true),
+        ("date", _) => ("NaiveDate".to_owned(), true),
+        ("datetime", _) => ("DateTime<Utc>".to_owned(), true),
+        ("many2one", Some(comodel)) => (format!("Many2one<{}>", comodel), true),
+        ("one2many", Some(comodel)) | ("many2many", Some(comodel)) => {
+            (format!("X2many<{}>", comodel), false)
+        }
+        ("binary", _) => return None,
+        _ => ("OdooValue".to_owned(), false),
+    };
+    if nullable && !field.required {
+        Some(format!("Option<{}>", type_))
+    } else {
+        Some(type_)
+    }
+}
+
+fn write_selection(out: &mut String, model: &str, name: &str, field: &FieldDescriptor) {
+    let enum_name = selection_name(model, name);
+    let mut variants: Vec<(String, &str, &str)> = Vec::new();
+    for (key, label) in &field.selection {
+        let mut variant = camel_case(key);
+        if variants.iter().any(|(v, _, _)| *v == variant) {
+            variant = format!("{}{}", variant, variants.len());
+        }
+        variants.push((variant, key, label));
+    }
+    let _ = writeln!(out, "\n/// `{}.{}` values", model, name);
+    let _ = writeln!(
+        out,
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, OdooSelection)]"
+    );
+    let _ = writeln!(out, "pub enum {} {{", enum_name);
+    for (variant, key, label) in &variants {
+        let _ = writeln!(
+            out,
+            "    #[odoo(key = {:?}, label = {:?})]",
+            key,
+            doc(label)
+        );
+        let _ = writeln!(out, "    {},", variant);
+    }
+    let _ = writeln!(out, "}}");
+}
+
+fn write_methods(out: &mut String, model: &str, methods: &[Method]) {
+    let mut methods: Vec<&Method> = methods.iter().collect();
+    methods.sort_by(|left, right| left.name.cmp(&right.name));
+    let _ = writeln!(out, "\nimpl {} {{", camel_case(model));
+    for method in methods {
+        let ident = method_name(&method.name);
+        let (receiver, records) = match method.kind {
+            MethodKind::Model => ("cli: &OdooClient", "cli.get_model(Self::MODEL)?".to_owned()),
+            MethodKind::Multi => (
+                "cli: &OdooClient, ids: &[u32]",
+                "cli.get_model(Self::MODEL)?.browse_lazy(ids)".to_owned(),
+            ),
+            MethodKind::One => (
+                "&self, cli: &OdooClient",
+                "cli.get_model(Self::MODEL)?.browse_lazy(&[self.id])".to_owned(),
+            ),
+        };
+        let _ = writeln!(out, "    /// `{}` ({:?})", method.name, method.kind);
+        let _ = writeln!(
+            out,
+            "    pub fn {}({}, args: impl Into<Args>, kwargs: Option<Value>) -> Result<Value> {{",
+            ident, receiver
+        );
+        let _ = writeln!(
+            out,
+            "        {}.call({:?}, args, kwargs)",
+            records, method.name
+        );
+        let _ = writeln!(out, "    }}");
+    }
+    let _ = writeln!(out, "}}");
+}
+
+fn write_model(
+    out: &mut String,
+    desc: &ObjectDescriptor,
+    methods: &[Method],
+    known: &BTreeSet<&str>,
+) {
+    let model = desc.name.as_str();
+    for (name, field) in &desc.fields {
+        if field.type_ == "selection" && !field.selection.is_empty() {
+            write_selection(out, model, name, field);
+        }
+    }
+    let _ = writeln!(out, "\n/// `{}`", model);
+    let _ = writeln!(out, "#[derive(Debug, Clone, PartialEq, OdooModel)]");
+    let _ = writeln!(out, "#[odoo(model = {:?})]", model);
+    let _ = writeln!(out, "pub struct {} {{", camel_case(model));
+    let _ = writeln!(out, "    pub id: u32,");
+    for (name, field) in desc.fields.iter().filter(|(name, _)| *name != "id") {
+        let type_ = match field_type(model, name, field, known) {
+            Some(type_) => type_,
+            None => {
+                let _ = writeln!(out, "    // {}: {} field, not mapped", name, field.type_);
+                continue;
+            }
+        };
+        if !field.string.is_empty() {
+            let _ = writeln!(out, "    /// {}", doc(&field.string));
+        }
+        if field.readonly || !field.store {
+            let _ = writeln!(out, "    #[odoo(readonly)]");
+        }
+        let ident = snake_case(name);
+        if ident != *name {
+            let _ = writeln!(out, "    #[odoo(rename = {:?})]", name);
+        }
+        let _ = writeln!(out, "    pub {}: {},", ident, type_);
+    }
+    let _ = writeln!(out, "}}");
+    if !methods.is_empty() {
+        write_methods(out, model, methods);
+    }
+}
+
+/// Rust source for `models` (descriptor and public methods of each model)
+///
+/// Relational fields are typed with `Many2one`/`X2many` when their comodel
+/// is generated too, as `OdooValue` otherwise.
+pub fn generate(models: &[(&ObjectDescriptor, &[Method])]) -> String {
+    let mut models = models.to_vec();
+    models.sort_by(|left, right| left.0.name.cmp(&right.0.name));
+    let known: BTreeSet<&str> = models.iter().map(|(desc, _)| desc.name.as_str()).collect();
+    let mut out = HEADER.to_owned();
+    for (desc, methods) in &models {
+        write_model(&mut out, desc, methods, &known);
+    }
+    out
+}
+
+impl OdooClient {
+    /// Rust source for models `names`, see `codegen::generate`
+    pub fn generate_source(&self, names: &[&str]) -> Result<String> {
+        let mut models = Vec::new();
+        for name in names {
+            let model = self.get_model(name)?;
+            let methods = model.get_methods()?;
+            models.push((model, methods));
+        }
+        let models: Vec<(&ObjectDescriptor, &[Method])> = models
+            .iter()
+            .map(|(model, methods)| (model.descriptor(), methods.as_slice()))
+            .collect();
+        Ok(generate(&models))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{camel_case, generate, method_name, snake_case};
+    use crate::{FieldDescriptor, Method, MethodKind, ObjectDescriptor};
+
+    fn field(type_: &str, relation: Option<&str>) -> FieldDescriptor {
+        FieldDescriptor {
+            type_: type_.to_owned(),
+            relation: relation.map(|r| r.to_owned()),
+            store: true,
+            ..Default::default()
+        }
+    }
+
+    fn descriptors() -> (ObjectDescriptor, ObjectDescriptor) {
+        let mut state = field("selection", None);
+        state.string = "Status".to_owned();
+        state.selection = vec![
+            ("draft".to_owned(), "Draft".to_owned()),
+            ("in_use".to_owned(), "In use".to_owned()),
+        ];
+        let mut name = field("char", None);
+        name.required = true;
+        let mut display_name = field("char", None);
+        display_name.store = false;
+        let label = ObjectDescriptor {
+            name: "stock.label".to_owned(),
+            fields: vec![
+                ("id".to_owned(), field("integer", None)),
+                ("name".to_owned(), name),
+                ("display_name".to_owned(), display_name),
+                ("state".to_owned(), state),
+                ("type".to_owned(), field("char", None)),
+                ("self".to_owned(), field("char", None)),
+                ("image".to_owned(), field("binary", None)),
+                (
+                    "product_id".to_owned(),
+                    field("many2one", Some("product.product")),
+                ),
+                (
+                    "location_id".to_owned(),
+                    field("many2one", Some("stock.location")),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let product = ObjectDescriptor {
+            name: "product.product".to_owned(),
+            fields: vec![("name".to_owned(), field("char", None))]
+                .into_iter()
+                .collect(),
+        };
+        (label, product)
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(camel_case("stock.label"), "StockLabel");
+        assert_eq!(camel_case("in_use"), "InUse");
+        assert_eq!(camel_case("0"), "V0");
+        assert_eq!(snake_case("type"), "r#type");
+        assert_eq!(snake_case("self"), "self_");
+        assert_eq!(method_name("check"), "check_");
+        assert_eq!(method_name("fields"), "fields_");
+        assert_eq!(method_name("close"), "close");
+    }
+
+    fn methods() -> Vec<Method> {
+        vec![
+            Method {
+                name: "close".to_owned(),
+                kind: MethodKind::Multi,
+            },
+            Method {
+                name: "batch_compute".to_owned(),
+                kind: MethodKind::Model,
+            },
+            Method {
+                name: "compute_kind".to_owned(),
+                kind: MethodKind::One,
+            },
+            Method {
+                name: "check".to_owned(),
+                kind: MethodKind::Multi,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_generate() {
+        let (label, product) = descriptors();
+        let methods = methods();
+        let source = generate(&[(&label, &methods), (&product, &[])]);
+        for expected in &[
+            "#[odoo(model = \"stock.label\")]\npub struct StockLabel {\n    pub id: u32,\n",
+            "    #[odoo(readonly)]\n    pub display_name: Option<String>,\n",
+            "    // image: binary field, not mapped\n",
+            "    pub location_id: OdooValue,\n",
+            "    pub name: String,\n",
+            "    pub product_id: Option<Many2one<ProductProduct>>,\n",
+            "    /// Status\n    pub state: Option<StockLabelState>,\n",
+            "    #[odoo(rename = \"type\")]\n    pub r#type: Option<String>,\n",
+            "    #[odoo(rename = \"self\")]\n    pub self_: Option<String>,\n",
+            "pub enum StockLabelState {\n    #[odoo(key = \"draft\", label = \"Draft\")]\n    Draft,\n",
+            "    #[odoo(key = \"in_use\", label = \"In use\")]\n    InUse,\n}\n",
+            "    pub fn batch_compute(cli: &OdooClient, args",
+            "    pub fn close(cli: &OdooClient, ids: &[u32], args",
+            "    pub fn compute_kind(&self, cli: &OdooClient, args",
+            "    pub fn check_(cli: &OdooClient, ids: &[u32], args",
+        ] {
+            assert!(source.contains(expected), "{}\nnot in\n{}", expected, source);
+        }
+        assert!(source.find("struct ProductProduct") < source.find("struct StockLabel"));
+        assert_eq!(
+            source,
+            generate(&[(&product, &[]), (&label, &methods)]),
+            "output does not depend on the order of the models"
+        );
+    }
+
+    /// `tests/generated/models.rs` is compiled by `tests/codegen.rs`
+    #[test]
+    fn test_generated_source() {
+        let (label, product) = descriptors();
+        let methods = methods();
+        assert_eq!(
+            generate(&[(&label, &methods), (&product, &[])]),
+            include_str!("../tests/generated/models.rs"),
+            "regenerate tests/generated/models.rs"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! x2many write commands
+//!
+//! one2many and many2many fields are written with Odoo's "magic tuples"
+//! `(0, 0, vals)`, `(4, id)`, `(6, 0, ids)`... `Command` builds them:
+//!
+//! ```ignore
+//! label.write(json!({"product_tag_ids": [Command::Link(3), Command::Unlink(4)]}))
+//! ```
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeTuple, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// x2many write command
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// `(0, 0, vals)`: create a new record and link it
+    Create(Value),
+    /// `(1, id, vals)`: update the linked record `id`
+    Update(u32, Value),
+    /// `(2, id, 0)`: unlink and delete the record `id`
+    Delete(u32),
+    /// `(3, id, 0)`: unlink the record `id`, without deleting it
+    Unlink(u32),
+    /// `(4, id, 0)`: link the existing record `id`
+    Link(u32),
+    /// `(5, 0, 0)`: unlink all records
+    Clear,
+    /// `(6, 0, ids)`: replace linked records by `ids`
+    Set(Vec<u32>),
+}
+
+impl Command {
+    /// numeric code of the command
+    pub fn code(&self) -> u8 {
+        match self {
+            Command::Create(_) => 0,
+            Command::Update(_, _) => 1,
+            Command::Delete(_) => 2,
+            Command::Unlink(_) => 3,
+            Command::Link(_) => 4,
+            Command::Clear => 5,
+            Command::Set(_) => 6,
+        }
+    }
+
+    /// does `value` look like a command tuple?
+    pub fn is_command(value: &Value) -> bool {
+        match value {
+            Value::Array(tuple) if !tuple.is_empty() && tuple.len() <= 3 => {
+                matches!(tuple[0].as_u64(), Some(code) if code <= 6)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Serialize for Command {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(3)?;
+        tuple.serialize_element(&self.code())?;
+        match self {
+            Command::Create(vals) => {
+                tuple.serialize_element(&0)?;
+                tuple.serialize_element(vals)?;
+            }
+            Command::Update(id, vals) => {
+                tuple.serialize_element(id)?;
+                tuple.serialize_element(vals)?;
+            }
+            Command::Delete(id) | Command::Unlink(id) | Command::Link(id) => {
+                tuple.serialize_element(id)?;
+                tuple.serialize_element(&0)?;
+            }
+            Command::Clear => {
+                tuple.serialize_element(&0)?;
+                tuple.serialize_element(&0)?;
+            }
+            Command::Set(ids) => {
+                tuple.serialize_element(&0)?;
+                tuple.serialize_element(ids)?;
+            }
+        }
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Command {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let tuple = Vec::<Value>::deserialize(deserializer)?;
+        let id = || {
+            tuple
+                .get(1)
+                .and_then(|id| id.as_u64())
+                .map(|id| id as u32)
+                .ok_or_else(|| de::Error::custom("command without record id"))
+        };
+        let vals = || tuple.get(2).cloned().unwrap_or_else(|| json!({}));
+        match tuple.first().and_then(|code| code.as_u64()) {
+            Some(0) => Ok(Command::Create(vals())),
+            Some(1) => Ok(Command::Update(id()?, vals())),
+            Some(2) => Ok(Command::Delete(id()?)),
+            Some(3) => Ok(Command::Unlink(id()?)),
+            Some(4) => Ok(Command::Link(id()?)),
+            Some(5) => Ok(Command::Clear),
+            Some(6) => serde_json::from_value(vals())
+                .map(Command::Set)
+                .map_err(de::Error::custom),
+            _ => Err(de::Error::custom(format!(
+                "invalid x2many command: {:?}",
+                tuple
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Command;
+    use serde_json::json;
+
+    #[test]
+    fn test_command_serialize() {
+        assert_eq!(
+            json!(Command::Create(json!({"name": "a"}))),
+            json!([0, 0, {"name": "a"}])
+        );
+        assert_eq!(
+            json!(Command::Update(7, json!({"name": "b"}))),
+            json!([1, 7, {"name": "b"}])
+        );
+        assert_eq!(json!(Command::Delete(7)), json!([2, 7, 0]));
+        assert_eq!(json!(Command::Unlink(7)), json!([3, 7, 0]));
+        assert_eq!(json!(Command::Link(7)), json!([4, 7, 0]));
+        assert_eq!(json!(Command::Clear), json!([5, 0, 0]));
+        assert_eq!(json!(Command::Set(vec![1, 2])), json!([6, 0, [1, 2]]));
+    }
+
+    #[test]
+    fn test_command_roundtrip() {
+        let commands = vec![
+            Command::Create(json!({"name": "a"})),
+            Command::Link(3),
+            Command::Clear,
+            Command::Set(vec![4, 5]),
+        ];
+        let decoded: Vec<Command> = serde_json::from_value(json!(commands)).unwrap();
+        assert_eq!(decoded, commands);
+        assert_eq!(
+            serde_json::from_value::<Command>(json!([4, 9])).unwrap(),
+            Command::Link(9)
+        );
+    }
+}
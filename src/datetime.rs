@@ -0,0 +1,84 @@
+//! date and datetime fields
+//!
+//! Odoo stores datetimes as naive UTC strings (`2021-02-14 10:00:00`) and
+//! dates as `2021-02-14`, whatever the timezone of the user.
+//!
+//! ```ignore
+//! let since = Utc::now() - Duration::days(7);
+//! let ids = labels.search(json!([("write_date", ">=", to_datetime_value(&since))]))?;
+//! let local = label.value("write_date").and_then(|v| v.to_tz(cli.user_tz()?));
+//! ```
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::Value;
+
+use crate::{OString, OdooClient};
+
+/// server format of date fields
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+/// server format of datetime fields (UTC)
+pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// parse a date sent by the server
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text, DATE_FORMAT).ok()
+}
+/// parse a naive UTC datetime sent by the server, fractional seconds are accepted
+pub fn parse_datetime(text: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").ok()
+}
+
+/// `date` as expected in `write` values and domain leaves
+pub fn to_date_value(date: &NaiveDate) -> Value {
+    Value::String(date.format(DATE_FORMAT).to_string())
+}
+/// `datetime` converted to UTC, as expected in `write` values and domain leaves
+pub fn to_datetime_value<T: TimeZone>(datetime: &DateTime<T>) -> Value {
+    Value::String(
+        datetime
+            .with_timezone(&Utc)
+            .naive_utc()
+            .format(DATETIME_FORMAT)
+            .to_string(),
+    )
+}
+
+impl OdooClient {
+    /// timezone of the logged user (`tz` of its context), if set and known
+    pub fn user_tz(&self) -> Option<Tz> {
+        match &self.session.as_ref()?.user_context.tz {
+            OString::Filled(tz) => tz.parse().ok(),
+            OString::Absent(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_date, parse_datetime, to_date_value, to_datetime_value};
+    use chrono::{NaiveDate, TimeZone};
+    use chrono_tz::Europe::Paris;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse_date("2021-02-14"),
+            NaiveDate::from_ymd_opt(2021, 2, 14)
+        );
+        assert_eq!(parse_date("14/02/2021"), None);
+        let datetime = NaiveDate::from_ymd_opt(2021, 2, 14)
+            .and_then(|d| d.and_hms_opt(10, 0, 0))
+            .unwrap();
+        assert_eq!(parse_datetime("2021-02-14 10:00:00"), Some(datetime));
+        assert_eq!(parse_datetime("2021-02-14 10:00:00.000"), Some(datetime));
+    }
+
+    #[test]
+    fn test_encode() {
+        let date = NaiveDate::from_ymd_opt(2021, 2, 14).unwrap();
+        assert_eq!(to_date_value(&date), json!("2021-02-14"));
+        let local = Paris.with_ymd_and_hms(2021, 2, 14, 11, 0, 0).unwrap();
+        assert_eq!(to_datetime_value(&local), json!("2021-02-14 10:00:00"));
+    }
+}
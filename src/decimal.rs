@@ -0,0 +1,155 @@
+//! exact float and monetary values (feature `decimal`)
+//!
+//! The RPC layer decodes JSON numbers as `f64`, values are rounded back to
+//! the precision of their field (`digits`) or of their currency. Decimals
+//! are written as strings, the server parses them without going through a
+//! binary float.
+//!
+//! ```ignore
+//! let total = invoice.record(0).unwrap().amount("amount_total")?;
+//! println!("{} {}", total.value, total.currency.map(|c| c.1).unwrap_or_default());
+//! line.write(json!({"price_unit": Decimal::new(1990, 2)}))?;
+//! ```
+use rust_decimal::prelude::FromPrimitive;
+pub use rust_decimal::Decimal;
+use serde_json::Value;
+
+use crate::typed::FieldValue;
+use crate::{Error, ErrorKind, OdooClient, OdooValue, Record, Result};
+
+/// monetary value with its currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct Amount {
+    pub value: Decimal,
+    /// `(id, display_name)` of the currency set in the `currency_field`
+    pub currency: Option<(u32, String)>,
+}
+
+/// decode an integer, float or monetary value, rounded to `scale` places
+///
+/// Without `scale`, trailing zeros are dropped.
+pub fn to_decimal(value: &OdooValue, scale: Option<u32>) -> Option<Decimal> {
+    let decimal = match value {
+        OdooValue::Integer(i) => return Some(Decimal::from(*i)),
+        OdooValue::Float(f) | OdooValue::Monetary(f) => Decimal::from_f64(*f)?,
+        _ => return None,
+    };
+    Some(match scale {
+        Some(scale) => {
+            let mut rounded = decimal.round_dp(scale);
+            rounded.rescale(scale);
+            rounded
+        }
+        None => decimal.normalize(),
+    })
+}
+
+/// decimal as written by `create` and `write`
+pub fn decimal_value(value: &Decimal) -> Value {
+    Value::String(value.to_string())
+}
+
+impl OdooClient {
+    /// decimal places of currency `id`
+    fn currency_places(&self, id: u32) -> Result<Option<u32>> {
+        let rows = self
+            .get_model("res.currency")?
+            .read(&[id], &["decimal_places"])?;
+        Ok(rows
+            .first()
+            .and_then(|row| row.get("decimal_places"))
+            .and_then(|places| places.as_u64())
+            .map(|places| places as u32))
+    }
+}
+
+impl Record<'_, '_> {
+    /// float or monetary field `name`, rounded to the `digits` of the field
+    pub fn decimal(&self, name: &str) -> Option<Decimal> {
+        let field = self.recordset().model.desc.fields.get(name)?;
+        to_decimal(&self.value(name)?, field.digits.map(|(_, scale)| scale))
+    }
+    /// monetary field `name` with its currency, rounded to the currency precision
+    pub fn amount(&self, name: &str) -> Result<Amount> {
+        let model = &self.recordset().model;
+        let field = match model.desc.fields.get(name) {
+            Some(field) if field.type_ == "monetary" => field,
+            _ => {
+                return Err(Error::from_kind(ErrorKind::InvalidField(format!(
+                    "{}.{} is not a monetary field",
+                    model.name(),
+                    name
+                ))))
+            }
+        };
+        let currency_field = field.currency_field.as_deref().unwrap_or("currency_id");
+        let currency = self.value(currency_field).and_then(|currency| {
+            currency
+                .as_many2one()
+                .map(|(id, name)| (id, name.to_owned()))
+        });
+        let scale = match &currency {
+            Some((id, _)) => model.cli.currency_places(*id)?,
+            None => None,
+        }
+        .or_else(|| field.digits.map(|(_, scale)| scale));
+        match self.value(name).and_then(|value| to_decimal(&value, scale)) {
+            Some(value) => Ok(Amount { value, currency }),
+            None => Err(Error::from_kind(ErrorKind::InvalidField(format!(
+                "{}.{} is not loaded for record {}",
+                model.name(),
+                name,
+                self.id()
+            )))),
+        }
+    }
+}
+
+impl FieldValue for Decimal {
+    fn kinds() -> &'static [&'static str] {
+        &["float", "monetary", "integer"]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        to_decimal(&value, None)
+    }
+    fn to_odoo(&self) -> Value {
+        decimal_value(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decimal_value, to_decimal, Decimal};
+    use crate::tests::{offline_client, offline_model, offline_records};
+    use crate::OdooValue;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_decimal() {
+        let sum = OdooValue::Float(0.1 + 0.2);
+        assert_eq!(to_decimal(&sum, Some(2)), Some(Decimal::new(30, 2)));
+        assert_eq!(to_decimal(&sum, Some(2)).unwrap().to_string(), "0.30");
+        assert_eq!(
+            to_decimal(&OdooValue::Monetary(19.9), None),
+            Some(Decimal::new(199, 1))
+        );
+        assert_eq!(
+            to_decimal(&OdooValue::Integer(3), Some(2)),
+            Some(Decimal::from(3))
+        );
+        assert_eq!(to_decimal(&OdooValue::Null, None), None);
+        assert_eq!(decimal_value(&Decimal::new(1990, 2)), json!("19.90"));
+    }
+
+    #[test]
+    fn test_record_decimal() {
+        let cli = offline_client();
+        let model = offline_model(&cli, "sim.card", &[("price", "float")]);
+        let cards = offline_records(&model, json!([{"id": 1, "price": 2.675}]));
+        assert_eq!(
+            cards.record(0).unwrap().decimal("price"),
+            Some(Decimal::new(2675, 3))
+        );
+        assert!(cards.record(0).unwrap().amount("price").is_err());
+    }
+}
@@ -0,0 +1,728 @@
+//! search domains
+//!
+//! A [`Domain`] is a tree of leaves `(field, operator, value)` combined with
+//! `&`, `|` and `!`. It is sent to the server in Odoo's prefix notation, and
+//! read back from it. `domain!` writes domains with Rust operators, values are
+//! Rust expressions:
+//!
+//! ```ignore
+//! let ids = labels.search(domain!(
+//!     product_id.default_code ilike code && (state == "draft" || !(is_terminal == true))
+//! ))?;
+//! let drafts = Domain::leaf("state", Operator::Eq, "draft").and(!Domain::leaf("name", Operator::Like, "X%"));
+//! ```
+//!
+//! Comparisons are `==`, `!=`, `<`, `<=`, `>`, `>=`, `in`, `not in`, `like`,
+//! `ilike`, `not like`, `not ilike`, `=like`, `=ilike`, `child_of` and
+//! `parent_of`. `!` binds tighter than `&&`, which binds tighter than `||`.
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::ops::Not;
+use std::slice;
+use std::str::FromStr;
+
+use crate::{Error, ErrorKind, Result};
+
+/// comparison operator of a domain leaf
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operator {
+    /// `=`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `=?`: true when the value is unset, `=` otherwise
+    EqOrUnset,
+    /// `=like`
+    EqLike,
+    /// `=ilike`
+    EqIlike,
+    /// `like`
+    Like,
+    /// `not like`
+    NotLike,
+    /// `ilike`
+    Ilike,
+    /// `not ilike`
+    NotIlike,
+    /// `in`
+    In,
+    /// `not in`
+    NotIn,
+    /// `child_of`
+    ChildOf,
+    /// `parent_of`
+    ParentOf,
+    /// `any` (Odoo 17+)
+    Any,
+    /// `not any` (Odoo 17+)
+    NotAny,
+}
+
+impl Operator {
+    /// every operator, as known by the server
+    pub const ALL: &'static [Operator] = &[
+        Operator::Eq,
+        Operator::Ne,
+        Operator::Lt,
+        Operator::Le,
+        Operator::Gt,
+        Operator::Ge,
+        Operator::EqOrUnset,
+        Operator::EqLike,
+        Operator::EqIlike,
+        Operator::Like,
+        Operator::NotLike,
+        Operator::Ilike,
+        Operator::NotIlike,
+        Operator::In,
+        Operator::NotIn,
+        Operator::ChildOf,
+        Operator::ParentOf,
+        Operator::Any,
+        Operator::NotAny,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operator::Eq => "=",
+            Operator::Ne => "!=",
+            Operator::Lt => "<",
+            Operator::Le => "<=",
+            Operator::Gt => ">",
+            Operator::Ge => ">=",
+            Operator::EqOrUnset => "=?",
+            Operator::EqLike => "=like",
+            Operator::EqIlike => "=ilike",
+            Operator::Like => "like",
+            Operator::NotLike => "not like",
+            Operator::Ilike => "ilike",
+            Operator::NotIlike => "not ilike",
+            Operator::In => "in",
+            Operator::NotIn => "not in",
+            Operator::ChildOf => "child_of",
+            Operator::ParentOf => "parent_of",
+            Operator::Any => "any",
+            Operator::NotAny => "not any",
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Operator {
+    type Err = Error;
+
+    /// parse an operator, `==` and `<>` are accepted like the server does
+    ///
+    /// Case matters: the server only knows the lowercase spelling (`ilike`,
+    /// `not in`), `IN` is refused.
+    fn from_str(text: &str) -> Result<Operator> {
+        match text {
+            "==" => Ok(Operator::Eq),
+            "<>" => Ok(Operator::Ne),
+            text => Operator::ALL
+                .iter()
+                .find(|op| op.as_str() == text)
+                .copied()
+                .ok_or_else(|| invalid(format!("unknown operator {:?}", text))),
+        }
+    }
+}
+
+/// search domain
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Domain {
+    /// every record, `[]` or `(1, '=', 1)`
+    #[default]
+    True,
+    /// no record, `(0, '=', 1)`
+    False,
+    /// `(field, op, value)`, `field` may be a dotted path (`partner_id.country_id`)
+    Leaf {
+        field: String,
+        op: Operator,
+        value: Value,
+    },
+    /// `&`: all of these domains
+    And(Vec<Domain>),
+    /// `|`: any of these domains
+    Or(Vec<Domain>),
+    /// `!`
+    Not(Box<Domain>),
+}
+
+fn invalid(message: String) -> Error {
+    Error::from_kind(ErrorKind::InvalidDomain(message))
+}
+
+/// parse the next expression of a prefix domain
+fn parse_term(terms: &mut slice::Iter<Value>) -> Result<Domain> {
+    match terms.next() {
+        None => Err(invalid("missing operand".to_owned())),
+        Some(Value::String(op)) if op == "&" => {
+            let left = parse_term(terms)?;
+            Ok(Domain::And(vec![left, parse_term(terms)?]))
+        }
+        Some(Value::String(op)) if op == "|" => {
+            let left = parse_term(terms)?;
+            Ok(Domain::Or(vec![left, parse_term(terms)?]))
+        }
+        Some(Value::String(op)) if op == "!" => Ok(Domain::Not(Box::new(parse_term(terms)?))),
+        Some(Value::String(op)) => Err(invalid(format!(
+            "unknown domain operator {:?}, expected \"&\", \"|\" or \"!\"",
+            op
+        ))),
+        Some(Value::Array(leaf)) => parse_leaf(leaf),
+        Some(other) => Err(invalid(format!(
+            "expected a leaf or an operator, got {}",
+            other
+        ))),
+    }
+}
+
+fn parse_leaf(leaf: &[Value]) -> Result<Domain> {
+    match leaf {
+        [Value::String(field), Value::String(op), value] => Ok(Domain::Leaf {
+            field: field.to_owned(),
+            op: op.parse()?,
+            value: value.to_owned(),
+        }),
+        [Value::Number(left), Value::String(op), Value::Number(right)]
+            if op == "=" && right.as_u64() == Some(1) && left.as_u64() == Some(1) =>
+        {
+            Ok(Domain::True)
+        }
+        [Value::Number(left), Value::String(op), Value::Number(right)]
+            if op == "=" && right.as_u64() == Some(1) && left.as_u64() == Some(0) =>
+        {
+            Ok(Domain::False)
+        }
+        _ => Err(invalid(format!(
+            "invalid leaf {}, expected [field, operator, value]",
+            Value::from(leaf.to_vec())
+        ))),
+    }
+}
+
+impl Domain {
+    /// leaf `(field, op, value)`
+    pub fn leaf<T: Serialize>(field: &str, op: Operator, value: T) -> Domain {
+        Domain::Leaf {
+            field: field.to_owned(),
+            op,
+            value: json!(value),
+        }
+    }
+
+    /// records matching both domains
+    pub fn and(self, other: Domain) -> Domain {
+        Domain::And(vec![self, other]).simplify()
+    }
+    /// records matching either domain
+    pub fn or(self, other: Domain) -> Domain {
+        Domain::Or(vec![self, other]).simplify()
+    }
+
+    /// parse a domain in prefix notation, top level expressions are and-ed
+    pub fn from_value(value: &Value) -> Result<Domain> {
+        let terms = match value {
+            Value::Array(terms) => terms,
+            other => return Err(invalid(format!("expected a list, got {}", other))),
+        };
+        let mut terms = terms.iter();
+        let mut domains = Vec::new();
+        while terms.len() > 0 {
+            domains.push(parse_term(&mut terms)?);
+        }
+        Ok(match domains.len() {
+            0 => Domain::True,
+            1 => domains.remove(0),
+            _ => Domain::And(domains),
+        })
+    }
+
+    fn write_prefix(&self, terms: &mut Vec<Value>) {
+        match self {
+            Domain::Leaf { field, op, value } => terms.push(json!([field, op.as_str(), value])),
+            Domain::And(domains) | Domain::Or(domains) if !domains.is_empty() => {
+                let op = if let Domain::And(_) = self { "&" } else { "|" };
+                terms.extend(vec![Value::from(op); domains.len() - 1]);
+                for domain in domains {
+                    domain.write_prefix(terms);
+                }
+            }
+            Domain::Not(domain) => {
+                terms.push(Value::from("!"));
+                domain.write_prefix(terms);
+            }
+            Domain::True | Domain::And(_) => terms.push(json!([1, "=", 1])),
+            Domain::False | Domain::Or(_) => terms.push(json!([0, "=", 1])),
+        }
+    }
+
+    /// this domain in Odoo's prefix notation, every operator explicit
+    pub fn to_value(&self) -> Value {
+        match self {
+            Domain::True => json!([]),
+            Domain::And(domains) if domains.is_empty() => json!([]),
+            _ => {
+                let mut terms = Vec::new();
+                self.write_prefix(&mut terms);
+                Value::Array(terms)
+            }
+        }
+    }
+
+    /// equivalent domain without constant leaves, nested `&`/`|` nor double `!`
+    pub fn simplify(self) -> Domain {
+        match self {
+            Domain::And(domains) => {
+                let mut simple = Vec::new();
+                for domain in domains {
+                    match domain.simplify() {
+                        Domain::True => {}
+                        Domain::False => return Domain::False,
+                        Domain::And(inner) => simple.extend(inner),
+                        domain => simple.push(domain),
+                    }
+                }
+                match simple.len() {
+                    0 => Domain::True,
+                    1 => simple.remove(0),
+                    _ => Domain::And(simple),
+                }
+            }
+            Domain::Or(domains) => {
+                let mut simple = Vec::new();
+                for domain in domains {
+                    match domain.simplify() {
+                        Domain::False => {}
+                        Domain::True => return Domain::True,
+                        Domain::Or(inner) => simple.extend(inner),
+                        domain => simple.push(domain),
+                    }
+                }
+                match simple.len() {
+                    0 => Domain::False,
+                    1 => simple.remove(0),
+                    _ => Domain::Or(simple),
+                }
+            }
+            Domain::Not(domain) => match domain.simplify() {
+                Domain::True => Domain::False,
+                Domain::False => Domain::True,
+                Domain::Not(inner) => *inner,
+                domain => Domain::Not(Box::new(domain)),
+            },
+            leaf => leaf,
+        }
+    }
+
+    /// every leaf of this domain, in order
+    pub fn leaves(&self) -> Vec<(&str, Operator, &Value)> {
+        match self {
+            Domain::True | Domain::False => vec![],
+            Domain::Leaf { field, op, value } => vec![(field.as_str(), *op, value)],
+            Domain::And(domains) | Domain::Or(domains) => {
+                domains.iter().flat_map(|domain| domain.leaves()).collect()
+            }
+            Domain::Not(domain) => domain.leaves(),
+        }
+    }
+}
+
+impl Not for Domain {
+    type Output = Domain;
+
+    fn not(self) -> Domain {
+        Domain::Not(Box::new(self)).simplify()
+    }
+}
+
+impl From<Domain> for Value {
+    fn from(domain: Domain) -> Value {
+        domain.to_value()
+    }
+}
+
+impl Serialize for Domain {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Domain {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        Domain::from_value(&value).map_err(de::Error::custom)
+    }
+}
+
+/// a [`Domain`], or its JSON form, checked before it is sent
+pub trait IntoDomain {
+    fn into_domain(self) -> Result<Domain>;
+}
+
+impl IntoDomain for Domain {
+    fn into_domain(self) -> Result<Domain> {
+        Ok(self)
+    }
+}
+
+impl IntoDomain for Value {
+    fn into_domain(self) -> Result<Domain> {
+        Domain::from_value(&self)
+    }
+}
+
+/// build a [`Domain`], see [`crate::domain`]
+#[macro_export]
+macro_rules! domain {
+    () => {
+        $crate::domain::Domain::True
+    };
+    ($($tokens:tt)+) => {
+        $crate::__domain_split!([] [] [] $($tokens)+)
+    };
+}
+
+/// split the terms on `&&` and `||`, taking up to four tokens per step
+///
+/// State: `[groups joined by ||] [terms of the current group] [tokens of the current term]`.
+/// Each term is then matched once by `__domain_term!`, which keeps the
+/// recursion depth low enough for long domains.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __domain_split {
+    ([$($groups:tt)*] [$($terms:tt)*] [$($term:tt)*] && $($rest:tt)+) => {
+        $crate::__domain_split!([$($groups)*] [$($terms)* [$($term)*]] [] $($rest)+)
+    };
+    ([$($groups:tt)*] [$($terms:tt)*] [$($term:tt)*] || $($rest:tt)+) => {
+        $crate::__domain_split!([$($groups)* [$($terms)* [$($term)*]]] [] [] $($rest)+)
+    };
+    ([$($groups:tt)*] [$($terms:tt)*] [$($term:tt)*] $a:tt && $($rest:tt)+) => {
+        $crate::__domain_split!([$($groups)*] [$($terms)* [$($term)* $a]] [] $($rest)+)
+    };
+    ([$($groups:tt)*] [$($terms:tt)*] [$($term:tt)*] $a:tt || $($rest:tt)+) => {
+        $crate::__domain_split!([$($groups)* [$($terms)* [$($term)* $a]]] [] [] $($rest)+)
+    };
+    ([$($groups:tt)*] [$($terms:tt)*] [$($term:tt)*] $a:tt $b:tt && $($rest:tt)+) => {
+        $crate::__domain_split!([$($groups)*] [$($terms)* [$($term)* $a $b]] [] $($rest)+)
+    };
+    ([$($groups:tt)*] [$($terms:tt)*] [$($term:tt)*] $a:tt $b:tt || $($rest:tt)+) => {
+        $crate::__domain_split!([$($groups)* [$($terms)* [$($term)* $a $b]]] [] [] $($rest)+)
+    };
+    ([$($groups:tt)*] [$($terms:tt)*] [$($term:tt)*] $a:tt $b:tt $c:tt && $($rest:tt)+) => {
+        $crate::__domain_split!([$($groups)*] [$($terms)* [$($term)* $a $b $c]] [] $($rest)+)
+    };
+    ([$($groups:tt)*] [$($terms:tt)*] [$($term:tt)*] $a:tt $b:tt $c:tt || $($rest:tt)+) => {
+        $crate::__domain_split!([$($groups)* [$($terms)* [$($term)* $a $b $c]]] [] [] $($rest)+)
+    };
+    // none of the next three tokens is `&&` or `||`, the fourth is checked by the next step
+    ([$($groups:tt)*] [$($terms:tt)*] [$($term:tt)*] $a:tt $b:tt $c:tt $d:tt $($rest:tt)*) => {
+        $crate::__domain_split!([$($groups)*] [$($terms)*] [$($term)* $a $b $c $d] $($rest)*)
+    };
+    ([$($groups:tt)*] [$($terms:tt)*] [$($term:tt)*] $($last:tt)*) => {
+        $crate::__domain_or!([$($groups)* [$($terms)* [$($term)* $($last)*]]])
+    };
+}
+
+/// `||` of groups of terms
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __domain_or {
+    ([[$($terms:tt)+]]) => {
+        $crate::__domain_and!($($terms)+)
+    };
+    ([$([$($terms:tt)+])+]) => {
+        $crate::domain::Domain::Or(::std::vec![$($crate::__domain_and!($($terms)+)),+])
+    };
+}
+
+/// `&&` of terms
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __domain_and {
+    ([$($term:tt)+]) => {
+        $crate::__domain_term!($($term)+)
+    };
+    ($([$($term:tt)+])+) => {
+        $crate::domain::Domain::And(::std::vec![$($crate::__domain_term!($($term)+)),+])
+    };
+}
+
+/// `!term`, `(domain)` or `path operator value`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __domain_term {
+    (! $($term:tt)+) => {
+        $crate::domain::Domain::Not(::std::boxed::Box::new($crate::__domain_term!($($term)+)))
+    };
+    (($($inner:tt)+)) => {
+        $crate::domain!($($inner)+)
+    };
+    ($first:ident $(. $more:ident)* == $($value:tt)+) => {
+        $crate::__domain_leaf!(Eq, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* != $($value:tt)+) => {
+        $crate::__domain_leaf!(Ne, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* <= $($value:tt)+) => {
+        $crate::__domain_leaf!(Le, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* >= $($value:tt)+) => {
+        $crate::__domain_leaf!(Ge, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* < $($value:tt)+) => {
+        $crate::__domain_leaf!(Lt, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* > $($value:tt)+) => {
+        $crate::__domain_leaf!(Gt, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* in $($value:tt)+) => {
+        $crate::__domain_leaf!(In, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* not in $($value:tt)+) => {
+        $crate::__domain_leaf!(NotIn, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* like $($value:tt)+) => {
+        $crate::__domain_leaf!(Like, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* ilike $($value:tt)+) => {
+        $crate::__domain_leaf!(Ilike, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* not like $($value:tt)+) => {
+        $crate::__domain_leaf!(NotLike, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* not ilike $($value:tt)+) => {
+        $crate::__domain_leaf!(NotIlike, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* = like $($value:tt)+) => {
+        $crate::__domain_leaf!(EqLike, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* = ilike $($value:tt)+) => {
+        $crate::__domain_leaf!(EqIlike, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* child_of $($value:tt)+) => {
+        $crate::__domain_leaf!(ChildOf, [$first $($more)*] $($value)+)
+    };
+    ($first:ident $(. $more:ident)* parent_of $($value:tt)+) => {
+        $crate::__domain_leaf!(ParentOf, [$first $($more)*] $($value)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __domain_leaf {
+    ($op:ident, [$first:ident $($more:ident)*] $($value:tt)+) => {
+        $crate::domain::Domain::Leaf {
+            field: ::std::concat!(::std::stringify!($first) $(, ".", ::std::stringify!($more))*)
+                .to_owned(),
+            op: $crate::domain::Operator::$op,
+            value: $crate::serde_json::json!($($value)+),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Domain, Operator};
+    use serde_json::json;
+
+    #[test]
+    fn test_domain_macro() {
+        let code = "APL";
+        let states = vec!["draft", "in_use"];
+        assert_eq!(domain!(), Domain::True);
+        assert_eq!(
+            domain!(is_terminal == true),
+            Domain::leaf("is_terminal", Operator::Eq, true)
+        );
+        assert_eq!(
+            domain!(product_id.default_code =ilike code).to_value(),
+            json!([["product_id.default_code", "=ilike", "APL"]])
+        );
+        assert_eq!(
+            domain!(state not in states && name like format!("{}%", code)).to_value(),
+            json!([
+                "&",
+                ["state", "not in", ["draft", "in_use"]],
+                ["name", "like", "APL%"]
+            ])
+        );
+        assert_eq!(
+            domain!(a == 1 && b != 2 || !c <= 3.5 && location_id child_of [1, 2]).to_value(),
+            json!([
+                "|",
+                "&",
+                ["a", "=", 1],
+                ["b", "!=", 2],
+                "&",
+                "!",
+                ["c", "<=", 3.5],
+                ["location_id", "child_of", [1, 2]]
+            ])
+        );
+        assert_eq!(
+            domain!(a > 1 && (b < 2 || c >= 3) && !(d not ilike "x" && e in [])).to_value(),
+            json!([
+                "&",
+                "&",
+                ["a", ">", 1],
+                "|",
+                ["b", "<", 2],
+                ["c", ">=", 3],
+                "!",
+                "&",
+                ["d", "not ilike", "x"],
+                ["e", "in", []]
+            ])
+        );
+    }
+
+    #[test]
+    fn test_long_domain_macro() {
+        let code = "APL";
+        let domain = domain!(
+            f1 == 1
+                && f2 == 2
+                && f3 == 3
+                && f4 == 4
+                && f5 == 5
+                && f6 == 6
+                && f7 == 7
+                && f8 == 8
+                && f9 == 9
+                && f10 == 10
+                && f11 == 11
+                && f12 == 12
+                && f13 == 13
+                && f14 == 14
+                && f15 == 15
+                && f16 == 16
+                && f17 == 17
+                && f18 == 18
+                && f19 == 19
+                && f20 == 20
+                && f21 == 21
+                && f22 == 22
+                && f23 == 23
+                && f24 == 24
+                || f25 == 25
+        );
+        assert_eq!(domain.leaves().len(), 25);
+        match domain {
+            Domain::Or(groups) => match &groups[0] {
+                Domain::And(terms) => assert_eq!(terms.len(), 24),
+                other => panic!("unexpected group {:?}", other),
+            },
+            other => panic!("unexpected domain {:?}", other),
+        }
+
+        let domain = domain!(
+            a.b.code ilike format!("{}1", code) && a.b.code ilike format!("{}2", code)
+                && a.b.code ilike format!("{}3", code) && a.b.code ilike format!("{}4", code)
+                && a.b.code ilike format!("{}5", code) && a.b.code ilike format!("{}6", code)
+                && a.b.code ilike format!("{}7", code) && a.b.code ilike format!("{}8", code)
+                && a.b.code ilike format!("{}9", code) && a.b.code ilike format!("{}10", code)
+                || a.b.code ilike format!("{}11", code) && a.b.code ilike format!("{}12", code)
+                || a.b.code ilike format!("{}13", code) && a.b.code ilike format!("{}14", code)
+                || a.b.code ilike format!("{}15", code) && a.b.code ilike format!("{}16", code)
+                || a.b.code ilike format!("{}17", code) && a.b.code ilike format!("{}18", code)
+                || !(a.b.code ilike format!("{}19", code) && a.b.code not ilike "x")
+        );
+        let leaves = domain.leaves();
+        assert_eq!(leaves.len(), 20);
+        assert_eq!(leaves[19], ("a.b.code", Operator::NotIlike, &json!("x")));
+        assert_eq!(leaves[18].2, &json!("APL19"));
+    }
+
+    #[test]
+    fn test_domain_value() {
+        let domain = Domain::from_value(&json!([
+            ["a", "=", 1],
+            "|",
+            ["b", "<>", 2],
+            "!",
+            ["c", "in", [3]]
+        ]))
+        .unwrap();
+        assert_eq!(
+            domain,
+            Domain::And(vec![
+                Domain::leaf("a", Operator::Eq, 1),
+                Domain::Or(vec![
+                    Domain::leaf("b", Operator::Ne, 2),
+                    Domain::Not(Box::new(Domain::leaf("c", Operator::In, [3]))),
+                ]),
+            ])
+        );
+        assert_eq!(
+            serde_json::to_value(&domain).unwrap(),
+            json!([
+                "&",
+                ["a", "=", 1],
+                "|",
+                ["b", "!=", 2],
+                "!",
+                ["c", "in", [3]]
+            ])
+        );
+        assert_eq!(
+            serde_json::from_value::<Domain>(json!([[1, "=", 1]])).unwrap(),
+            Domain::True
+        );
+
+        let err = |value| Domain::from_value(&value).unwrap_err().to_string();
+        assert!(err(json!([["a", "="]])).contains("expected [field, operator, value]"));
+        assert!(err(json!([["a", "~", 1]])).contains("unknown operator \"~\""));
+        assert!(err(json!([["a", "IN", [1]]])).contains("unknown operator \"IN\""));
+        assert!(err(json!([["a", "Like", "x"]])).contains("unknown operator \"Like\""));
+        assert!(err(json!(["|", ["a", "=", 1]])).contains("missing operand"));
+        assert!(err(json!(["^", ["a", "=", 1]])).contains("unknown domain operator"));
+    }
+
+    #[test]
+    fn test_simplify() {
+        let a = Domain::leaf("a", Operator::Eq, 1);
+        let b = Domain::leaf("b", Operator::Eq, 2);
+        let c = Domain::leaf("c", Operator::Eq, 3);
+        assert_eq!(a.clone().and(Domain::True), a);
+        assert_eq!(a.clone().and(Domain::False), Domain::False);
+        assert_eq!(a.clone().or(Domain::True), Domain::True);
+        assert_eq!(!!a.clone(), a);
+        assert_eq!(!Domain::True, Domain::False);
+        assert_eq!(
+            a.clone().and(b.clone()).and(c.clone()),
+            Domain::And(vec![a.clone(), b.clone(), c])
+        );
+        assert_eq!(
+            Domain::Or(vec![Domain::Or(vec![a.clone(), Domain::False]), b.clone()]).simplify(),
+            Domain::Or(vec![a.clone(), b])
+        );
+        assert_eq!(Domain::And(vec![]).to_value(), json!([]));
+        assert_eq!(Domain::Or(vec![]).to_value(), json!([[0, "=", 1]]));
+        assert_eq!(a.leaves(), vec![("a", Operator::Eq, &json!(1))]);
+    }
+}
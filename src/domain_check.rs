@@ -0,0 +1,346 @@
+//! domains checked against field descriptors
+//!
+//! Leaves are checked before a domain is sent, so that a typo
+//! (`is_terminla`) is reported with the model and the leaf at fault rather
+//! than a server traceback. Dotted paths are followed through the comodels.
+//! The check is opt-in, see [`crate::Model::with_domain_check`].
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use std::rc::Rc;
+
+use crate::datetime::{parse_date, parse_datetime};
+use crate::validate::is_id;
+use crate::{
+    Domain, Error, ErrorKind, FieldDescriptor, ObjectDescriptor, OdooClient, Operator, Result,
+};
+
+type Comodel<'c> = dyn FnMut(&str) -> Result<Rc<ObjectDescriptor>> + 'c;
+
+fn is_relational(type_: &str) -> bool {
+    matches!(type_, "many2one" | "one2many" | "many2many")
+}
+
+/// date or datetime as the server accepts it in a leaf, seconds are optional
+fn is_date(text: &str) -> bool {
+    parse_date(text).is_some()
+        || parse_datetime(text).is_some()
+        || NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").is_ok()
+}
+
+/// does `value` fit a field of type `type_`? (`false` always does)
+///
+/// Only values the server would reject are refused: numbers compare with
+/// any numeric field and with text fields.
+fn fits(type_: &str, value: &Value) -> bool {
+    if matches!(value, Value::Null | Value::Bool(false)) {
+        return true;
+    }
+    match type_ {
+        "boolean" => value.is_boolean(),
+        "integer" | "float" | "monetary" => value.is_number(),
+        "char" | "text" | "html" => value.is_string() || value.is_number(),
+        "selection" => value.is_string(),
+        "date" | "datetime" => matches!(value.as_str(), Some(text) if is_date(text)),
+        // ids, or names searched with `name_search`
+        "many2one" | "one2many" | "many2many" => is_id(value) || value.is_string(),
+        _ => true,
+    }
+}
+
+/// what is wrong with `op value` on `field`, if anything
+fn check_operator(field: &FieldDescriptor, op: Operator, value: &Value) -> Option<String> {
+    let type_ = field.type_.as_str();
+    let values = match (op, value) {
+        (Operator::In, Value::Array(values)) | (Operator::NotIn, Value::Array(values)) => {
+            values.iter().collect()
+        }
+        (Operator::In, _) | (Operator::NotIn, _) => {
+            return Some(format!("{} expects a list, got {}", op, value))
+        }
+        (Operator::ChildOf, Value::Array(values)) | (Operator::ParentOf, Value::Array(values)) => {
+            values.iter().collect()
+        }
+        _ => vec![value],
+    };
+    match op {
+        Operator::Like
+        | Operator::NotLike
+        | Operator::Ilike
+        | Operator::NotIlike
+        | Operator::EqLike
+        | Operator::EqIlike => {
+            if matches!(type_, "boolean" | "binary") {
+                return Some(format!("{} does not apply to {} fields", op, type_));
+            }
+            return None;
+        }
+        Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge
+            if matches!(type_, "boolean" | "binary" | "one2many" | "many2many") =>
+        {
+            return Some(format!("{} does not apply to {} fields", op, type_));
+        }
+        Operator::ChildOf | Operator::ParentOf | Operator::Any | Operator::NotAny
+            if !is_relational(type_) =>
+        {
+            return Some(format!("{} needs a relational field, not {}", op, type_));
+        }
+        Operator::Any | Operator::NotAny => return None,
+        _ => {}
+    }
+    if let Some(value) = values.iter().find(|value| !fits(type_, value)) {
+        let expected = if is_relational(type_) {
+            "a record id".to_owned()
+        } else if type_.starts_with(|c| "aeiou".contains(c)) {
+            format!("an {} value", type_)
+        } else {
+            format!("a {} value", type_)
+        };
+        return Some(format!("expected {}, got {}", expected, value));
+    }
+    let keyed = matches!(
+        op,
+        Operator::Eq | Operator::Ne | Operator::In | Operator::NotIn
+    );
+    if type_ == "selection" && keyed && !field.selection.is_empty() {
+        if let Some(value) = values.iter().find(|value| !field.accepts(value)) {
+            return Some(format!(
+                "{} is not one of {}",
+                value,
+                field.selection_keys().join(", ")
+            ));
+        }
+    }
+    None
+}
+
+impl ObjectDescriptor {
+    fn is_searchable(&self, name: &str) -> bool {
+        let searchable = self.get_searchable_fields();
+        // older servers do not send `searchable` at all
+        searchable.is_empty() || searchable.iter().any(|(field, _)| field == name)
+    }
+
+    /// check the leaves of `domain`, fetching comodel descriptors from `cli`
+    ///
+    /// Every leaf field must exist and be searchable, dotted paths must go
+    /// through relational fields, the operator must fit the field type and so
+    /// must the value (a list for `in`, ids for relational fields, known keys
+    /// for selections). All problems are reported in one
+    /// [`ErrorKind::DomainMismatch`] error.
+    pub fn validate_domain(&self, domain: &Domain, cli: &OdooClient) -> Result<()> {
+        self.validate_domain_with(domain, |name| {
+            cli.get_model(name).map(|model| Rc::clone(&model.desc))
+        })
+    }
+
+    /// check the leaves of `domain`, `comodel` returns the descriptor of a model
+    pub fn validate_domain_with<F>(&self, domain: &Domain, mut comodel: F) -> Result<()>
+    where
+        F: FnMut(&str) -> Result<Rc<ObjectDescriptor>>,
+    {
+        let mut problems = Vec::new();
+        self.collect_domain_problems(domain, &mut comodel, &mut problems)?;
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::from_kind(ErrorKind::DomainMismatch(
+                self.name.to_owned(),
+                problems,
+            )))
+        }
+    }
+
+    fn collect_domain_problems(
+        &self,
+        domain: &Domain,
+        comodel: &mut Comodel,
+        problems: &mut Vec<String>,
+    ) -> Result<()> {
+        for (path, op, value) in domain.leaves() {
+            if let Some(problem) = self.check_leaf(path, op, value, comodel, problems)? {
+                problems.push(format!("{} {} {}: {}", path, op, value, problem));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_leaf(
+        &self,
+        path: &str,
+        op: Operator,
+        value: &Value,
+        comodel: &mut Comodel,
+        problems: &mut Vec<String>,
+    ) -> Result<Option<String>> {
+        let segments: Vec<&str> = path.split('.').collect();
+        let mut followed: Option<Rc<ObjectDescriptor>> = None;
+        for (i, segment) in segments.iter().enumerate() {
+            let current = followed.as_deref().unwrap_or(self);
+            let last = i + 1 == segments.len();
+            let field = match current.fields.get(*segment) {
+                Some(field) => field,
+                None if *segment == "id" && last => {
+                    // `id child_of 3` walks the model's own hierarchy
+                    let hierarchy = matches!(op, Operator::ChildOf | Operator::ParentOf);
+                    let id = FieldDescriptor {
+                        type_: if hierarchy { "many2one" } else { "integer" }.to_owned(),
+                        ..Default::default()
+                    };
+                    return Ok(check_operator(&id, op, value));
+                }
+                None => return Ok(Some(format!("{} has no field {}", current.name, segment))),
+            };
+            if !current.is_searchable(segment) {
+                return Ok(Some(format!(
+                    "{}.{} is not searchable",
+                    current.name, segment
+                )));
+            }
+            let relation = match &field.relation {
+                Some(relation) if is_relational(&field.type_) => relation,
+                _ if last => return Ok(check_operator(field, op, value)),
+                _ => {
+                    return Ok(Some(format!(
+                        "{}.{} is a {} field, it can not be followed",
+                        current.name, segment, field.type_
+                    )))
+                }
+            };
+            if last {
+                if let (Operator::Any, _) | (Operator::NotAny, _) = (op, value) {
+                    let sub = match Domain::from_value(value) {
+                        Ok(sub) => sub,
+                        Err(err) => return Ok(Some(err.to_string())),
+                    };
+                    comodel(relation)?.collect_domain_problems(&sub, comodel, problems)?;
+                }
+                return Ok(check_operator(field, op, value));
+            }
+            followed = Some(comodel(relation)?);
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Domain, ErrorKind, FieldDescriptor, ObjectDescriptor};
+    use serde_json::json;
+    use std::rc::Rc;
+
+    fn field(type_: &str, relation: Option<&str>) -> FieldDescriptor {
+        FieldDescriptor {
+            type_: type_.to_owned(),
+            relation: relation.map(str::to_owned),
+            searchable: true,
+            ..Default::default()
+        }
+    }
+
+    fn descriptor(name: &str, fields: Vec<(&str, FieldDescriptor)>) -> ObjectDescriptor {
+        ObjectDescriptor {
+            name: name.to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(name, field)| (name.to_owned(), field))
+                .collect(),
+        }
+    }
+
+    fn problems(domain: serde_json::Value) -> Vec<String> {
+        let product = Rc::new(descriptor(
+            "product.product",
+            vec![
+                ("default_code", field("char", None)),
+                ("categ_id", field("many2one", Some("product.category"))),
+            ],
+        ));
+        let label = descriptor(
+            "stock.label",
+            vec![
+                ("name", field("char", None)),
+                ("is_terminal", field("boolean", None)),
+                ("qty", field("float", None)),
+                ("copies", field("integer", None)),
+                ("printed_on", field("date", None)),
+                ("product_id", field("many2one", Some("product.product"))),
+                ("tag_ids", field("many2many", Some("product.tag"))),
+                (
+                    "note",
+                    FieldDescriptor {
+                        searchable: false,
+                        ..field("text", None)
+                    },
+                ),
+                (
+                    "state",
+                    FieldDescriptor {
+                        selection: vec![("draft".to_owned(), "Draft".to_owned())],
+                        ..field("selection", None)
+                    },
+                ),
+            ],
+        );
+        let domain = Domain::from_value(&domain).unwrap();
+        let result = label.validate_domain_with(&domain, |name| match name {
+            "product.product" => Ok(Rc::clone(&product)),
+            other => panic!("unexpected comodel {}", other),
+        });
+        match result {
+            Ok(()) => vec![],
+            Err(err) => match err.0 {
+                ErrorKind::DomainMismatch(_, problems) => problems,
+                other => panic!("unexpected error {}", other),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_domain() {
+        assert_eq!(
+            problems(json!([
+                "|",
+                ["is_terminal", "=", true],
+                ["product_id.default_code", "ilike", "APL"],
+                ["product_id", "in", [1, 2]],
+                ["id", "child_of", 3],
+                ["printed_on", ">=", "2021-03-01"],
+                ["printed_on", "<", "2021-03-01 10:30"],
+                ["qty", ">", 1],
+                ["name", "=", 14],
+                ["state", "!=", "draft"],
+                ["product_id", "any", [["categ_id", "=", 4]]],
+                ["tag_ids", "=", false]
+            ])),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            problems(json!([
+                ["is_terminla", "=", true],
+                ["note", "ilike", "x"],
+                ["name.id", "=", 1],
+                ["product_id.default_cod", "=", "APL"],
+                ["qty", "in", 3],
+                ["is_terminal", "like", "t"],
+                ["product_id", "=", 1.5],
+                ["copies", "=", "2"],
+                ["state", "=", "done"],
+                ["qty", "child_of", 1],
+                ["product_id", "any", [["categ", "=", 4]]]
+            ])),
+            vec![
+                "is_terminla = true: stock.label has no field is_terminla",
+                "note ilike \"x\": stock.label.note is not searchable",
+                "name.id = 1: stock.label.name is a char field, it can not be followed",
+                "product_id.default_cod = \"APL\": product.product has no field default_cod",
+                "qty in 3: in expects a list, got 3",
+                "is_terminal like \"t\": like does not apply to boolean fields",
+                "product_id = 1.5: expected a record id, got 1.5",
+                "copies = \"2\": expected an integer value, got \"2\"",
+                "state = \"done\": \"done\" is not one of draft",
+                "qty child_of 1: child_of needs a relational field, not float",
+                "categ = 4: product.product has no field categ",
+            ]
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! domains evaluated in memory
+//!
+//! [`Domain::matches`] applies Odoo's search semantics to a record read as
+//! JSON (`{"id": 3, "name": "L-0001", "product_id": [7, "Apple"], ...}`),
+//! without a round trip:
+//!
+//! ```ignore
+//! let row = json!({"id": 3, "state": "draft", "product_id": [7, "Apple"]});
+//! assert!(domain!(state == "draft" && product_id == "Apple").matches(row.as_object().unwrap())?);
+//! let drafts = labels.filtered_domain(domain!(product_id.default_code ilike "APL"))?;
+//! ```
+//!
+//! Many2one values compare by id against numbers and by name against strings,
+//! x2many values match when any of their ids does. `false`, `null` and missing
+//! values are all unset. `child_of` and `parent_of` need the server.
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+use crate::{Domain, Error, ErrorKind, Operator, Result};
+
+fn is_unset(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => true,
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+/// `[id, "name"]` as read for a many2one field
+fn as_many2one(value: &Value) -> Option<(&Value, &str)> {
+    match value {
+        Value::Array(pair) => match pair.as_slice() {
+            [id @ Value::Number(_), Value::String(name)] => Some((id, name)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn scalar_eq(stored: &Value, value: &Value) -> bool {
+    match (stored, value) {
+        (Value::Number(stored), Value::Number(value)) => stored.as_f64() == value.as_f64(),
+        _ => stored == value,
+    }
+}
+
+fn equals(stored: &Value, value: &Value) -> bool {
+    if is_unset(value) {
+        return is_unset(stored);
+    }
+    if let Some((id, name)) = as_many2one(stored) {
+        return match value {
+            Value::String(value) => name == value,
+            value => scalar_eq(id, value),
+        };
+    }
+    match stored {
+        Value::Array(ids) => ids.iter().any(|id| scalar_eq(id, value)),
+        stored => scalar_eq(stored, value),
+    }
+}
+
+fn order(stored: &Value, value: &Value) -> Option<Ordering> {
+    let stored = as_many2one(stored).map_or(stored, |(id, _)| id);
+    match (stored, value) {
+        (Value::Number(stored), Value::Number(value)) => {
+            stored.as_f64()?.partial_cmp(&value.as_f64()?)
+        }
+        (Value::String(stored), Value::String(value)) => Some(stored.as_str().cmp(value)),
+        _ => None,
+    }
+}
+
+/// does `text` match `pattern`, `%` standing for any text and `_` for one character?
+fn like(text: &[char], pattern: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('%', rest)) => (0..=text.len()).any(|skip| like(&text[skip..], rest)),
+        Some((head, rest)) => match text.split_first() {
+            Some((first, text)) if *head == '_' || head == first => like(text, rest),
+            _ => false,
+        },
+    }
+}
+
+fn text_of(stored: &Value) -> Option<String> {
+    if let Some((_, name)) = as_many2one(stored) {
+        return Some(name.to_owned());
+    }
+    match stored {
+        Value::String(text) => Some(text.to_owned()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+fn matches_like(stored: &Value, op: Operator, value: &Value) -> bool {
+    let pattern = match value {
+        Value::String(pattern) => pattern,
+        // `name ilike false` looks for unset names
+        _ => return is_unset(stored),
+    };
+    let pattern = match op {
+        Operator::EqLike | Operator::EqIlike => pattern.to_owned(),
+        _ => format!("%{}%", pattern),
+    };
+    let insensitive = matches!(op, Operator::Ilike | Operator::NotIlike | Operator::EqIlike);
+    let chars = |text: &str| -> Vec<char> {
+        if insensitive {
+            text.to_lowercase().chars().collect()
+        } else {
+            text.chars().collect()
+        }
+    };
+    match text_of(stored) {
+        Some(text) => like(&chars(&text), &chars(&pattern)),
+        None => false,
+    }
+}
+
+fn values(value: &Value) -> &[Value] {
+    match value {
+        Value::Array(values) => values,
+        value => std::slice::from_ref(value),
+    }
+}
+
+/// does `stored` satisfy `op value`?
+fn compare(stored: &Value, op: Operator, value: &Value) -> Result<bool> {
+    Ok(match op {
+        Operator::Eq => equals(stored, value),
+        Operator::Ne => !equals(stored, value),
+        Operator::EqOrUnset => is_unset(value) || equals(stored, value),
+        Operator::In => values(value).iter().any(|value| equals(stored, value)),
+        Operator::NotIn => !values(value).iter().any(|value| equals(stored, value)),
+        Operator::Lt => order(stored, value) == Some(Ordering::Less),
+        Operator::Le => matches!(order(stored, value), Some(Ordering::Less | Ordering::Equal)),
+        Operator::Gt => order(stored, value) == Some(Ordering::Greater),
+        Operator::Ge => matches!(
+            order(stored, value),
+            Some(Ordering::Greater | Ordering::Equal)
+        ),
+        Operator::Like | Operator::Ilike | Operator::EqLike | Operator::EqIlike => {
+            matches_like(stored, op, value)
+        }
+        Operator::NotLike => !matches_like(stored, Operator::Like, value),
+        Operator::NotIlike => !matches_like(stored, Operator::Ilike, value),
+        Operator::ChildOf | Operator::ParentOf | Operator::Any | Operator::NotAny => {
+            return Err(Error::from_kind(ErrorKind::InvalidDomain(format!(
+                "{} can not be evaluated in memory",
+                op
+            ))))
+        }
+    })
+}
+
+impl Domain {
+    /// does `record`, as read from the server, match this domain?
+    ///
+    /// Fields missing from `record` are unset. Dotted paths, `any`, `not any`,
+    /// `child_of` and `parent_of` are errors, see
+    /// [`crate::RecordSet::filtered_domain`] to follow relations.
+    pub fn matches(&self, record: &Map<String, Value>) -> Result<bool> {
+        match self {
+            Domain::True => Ok(true),
+            Domain::False => Ok(false),
+            Domain::Leaf { field, op, value } => {
+                if field.contains('.') {
+                    return Err(Error::from_kind(ErrorKind::InvalidDomain(format!(
+                        "{} can not be followed in memory",
+                        field
+                    ))));
+                }
+                compare(record.get(field).unwrap_or(&Value::Null), *op, value)
+            }
+            Domain::And(domains) => {
+                for domain in domains {
+                    if !domain.matches(record)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Domain::Or(domains) => {
+                for domain in domains {
+                    if domain.matches(record)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Domain::Not(domain) => Ok(!domain.matches(record)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Domain;
+    use serde_json::{json, Value};
+
+    fn matching(domain: Value) -> Vec<u64> {
+        let records = json!([
+            {"id": 1, "name": "L-0001", "qty": 2, "state": "draft", "is_terminal": true,
+             "printed_on": "2021-03-01", "product_id": [7, "Apple"], "tag_ids": [3, 4]},
+            {"id": 2, "name": "l-0002", "qty": 0.5, "state": "done", "is_terminal": false,
+             "printed_on": false, "product_id": false, "tag_ids": []},
+            {"id": 3, "name": false, "qty": 10, "state": "draft",
+             "product_id": [8, "Pear"], "tag_ids": [4]},
+        ]);
+        let domain = Domain::from_value(&domain).unwrap();
+        records
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|record| domain.matches(record.as_object().unwrap()).unwrap())
+            .map(|record| record["id"].as_u64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_matches() {
+        assert_eq!(matching(json!([])), vec![1, 2, 3]);
+        assert_eq!(matching(json!([["state", "=", "draft"]])), vec![1, 3]);
+        assert_eq!(matching(json!([["state", "!=", "draft"]])), vec![2]);
+        assert_eq!(matching(json!([["qty", ">", 1]])), vec![1, 3]);
+        assert_eq!(matching(json!([["qty", "<=", 2.0]])), vec![1, 2]);
+        assert_eq!(
+            matching(json!([["printed_on", ">=", "2021-01-01"]])),
+            vec![1]
+        );
+        assert_eq!(matching(json!([["id", "in", [1, 3]]])), vec![1, 3]);
+        assert_eq!(matching(json!([["id", "not in", [1, 3]]])), vec![2]);
+        assert_eq!(matching(json!([["is_terminal", "=", false]])), vec![2, 3]);
+        assert_eq!(matching(json!([["name", "!=", false]])), vec![1, 2]);
+        assert_eq!(matching(json!([["name", "=?", false]])), vec![1, 2, 3]);
+        assert_eq!(matching(json!([["name", "=?", "L-0001"]])), vec![1]);
+    }
+
+    #[test]
+    fn test_matches_like() {
+        assert_eq!(matching(json!([["name", "like", "L-"]])), vec![1]);
+        assert_eq!(matching(json!([["name", "ilike", "L-"]])), vec![1, 2]);
+        assert_eq!(matching(json!([["name", "not ilike", "0001"]])), vec![2, 3]);
+        assert_eq!(matching(json!([["name", "=like", "L-%1"]])), vec![1]);
+        assert_eq!(matching(json!([["name", "=ilike", "l-000_"]])), vec![1, 2]);
+        assert_eq!(matching(json!([["name", "ilike", false]])), vec![3]);
+        assert_eq!(matching(json!([["product_id", "ilike", "pp"]])), vec![1]);
+    }
+
+    #[test]
+    fn test_matches_relational() {
+        assert_eq!(matching(json!([["product_id", "=", 7]])), vec![1]);
+        assert_eq!(matching(json!([["product_id", "=", "Pear"]])), vec![3]);
+        assert_eq!(
+            matching(json!([["product_id", "in", ["Apple", 8]]])),
+            vec![1, 3]
+        );
+        assert_eq!(matching(json!([["product_id", "=", false]])), vec![2]);
+        assert_eq!(matching(json!([["tag_ids", "=", 4]])), vec![1, 3]);
+        assert_eq!(matching(json!([["tag_ids", "not in", [3]]])), vec![2, 3]);
+        assert_eq!(matching(json!([["tag_ids", "!=", false]])), vec![1, 3]);
+        assert_eq!(
+            matching(json!([
+                "|",
+                "!",
+                ["state", "=", "draft"],
+                ["product_id", "=", 8]
+            ])),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn test_matches_errors() {
+        let record = json!({"id": 1});
+        let record = record.as_object().unwrap();
+        for domain in &[
+            json!([["product_id.name", "=", "Apple"]]),
+            json!([["id", "child_of", 1]]),
+        ] {
+            assert!(Domain::from_value(domain).unwrap().matches(record).is_err());
+        }
+    }
+}
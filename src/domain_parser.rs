@@ -0,0 +1,622 @@
+//! domains written as Python literals
+//!
+//! Action domains, record rules (`ir.rule.domain_force`), filters and the
+//! `domain` attribute of `fields_get` are Python source. The subset they use
+//! is parsed into a [`Domain`]: lists, tuples, strings, numbers,
+//! `True`/`False`/`None`, and placeholders substituted from [`Placeholders`]
+//! (`uid`, `company_ids`, `user.company_ids.ids`,
+//! `context_today().strftime('%Y-%m-%d')`...).
+//!
+//! ```ignore
+//! let rule = Domain::parse_with(
+//!     "['|', ('user_id', '=', uid), ('user_id', '=', False)]",
+//!     &cli.placeholders(),
+//! )?;
+//! ```
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::{json, Number, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::slice;
+
+use crate::datetime::DATE_FORMAT;
+use crate::{Domain, Error, ErrorKind, OdooClient, Operator, Result, SessionInfo};
+
+/// values of the names used by a domain (`uid`, `context_today()`)
+#[derive(Debug, Clone, Default)]
+pub struct Placeholders {
+    names: BTreeMap<String, Value>,
+    today: Option<NaiveDate>,
+}
+
+impl Placeholders {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// substitute `name` (possibly dotted, `user.company_id.id`) with `value`
+    pub fn with<T: Serialize>(mut self, name: &str, value: T) -> Self {
+        self.names.insert(name.to_owned(), json!(value));
+        self
+    }
+    /// date returned by `context_today()`
+    pub fn with_today(mut self, today: NaiveDate) -> Self {
+        self.today = Some(today);
+        self
+    }
+}
+
+/// companies the user may switch to, the current one if the server does not tell
+fn allowed_company_ids(session: &SessionInfo) -> Vec<u32> {
+    let allowed: Vec<u32> = match session.user_companies.get("allowed_companies") {
+        Some(Value::Object(companies)) => {
+            companies.keys().filter_map(|id| id.parse().ok()).collect()
+        }
+        // 13.0 sends `[[id, name], ...]`
+        Some(Value::Array(companies)) => companies
+            .iter()
+            .filter_map(|company| company.get(0).and_then(|id| id.as_u64()))
+            .map(|id| id as u32)
+            .collect(),
+        _ => vec![],
+    };
+    if allowed.is_empty() {
+        vec![session.company_id]
+    } else {
+        allowed
+    }
+}
+
+impl OdooClient {
+    /// placeholders of the logged user: `uid`, `company_id`, `company_ids`,
+    /// `user.company_ids.ids` and `context_today()`
+    ///
+    /// Calls are made in the current company only, so `company_ids` holds it
+    /// alone.
+    pub fn placeholders(&self) -> Placeholders {
+        let now = Utc::now();
+        let today = match self.user_tz() {
+            Some(tz) => now.with_timezone(&tz).naive_local().date(),
+            None => now.naive_utc().date(),
+        };
+        let placeholders = Placeholders::new().with_today(today);
+        match &self.session {
+            Some(session) => placeholders
+                .with("uid", session.uid)
+                .with("user.id", session.uid)
+                .with("company_id", session.company_id)
+                .with("user.company_id.id", session.company_id)
+                .with("company_ids", [session.company_id])
+                .with("user.company_ids.ids", allowed_company_ids(session)),
+            None => placeholders,
+        }
+    }
+}
+
+/// parsed value with its position, to report errors where they are
+struct Node {
+    at: usize,
+    kind: Kind,
+}
+
+enum Kind {
+    Scalar(Value),
+    List(Vec<Node>),
+}
+
+impl Node {
+    fn to_value(&self) -> Value {
+        match &self.kind {
+            Kind::Scalar(value) => value.to_owned(),
+            Kind::List(items) => Value::Array(items.iter().map(Node::to_value).collect()),
+        }
+    }
+}
+
+/// intermediate result of a placeholder expression
+enum Expr {
+    Value(Value),
+    Date(NaiveDate),
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    pos: usize,
+    placeholders: &'t Placeholders,
+}
+
+impl<'t> Parser<'t> {
+    fn error<T>(&self, at: usize, message: String) -> Result<T> {
+        let before = &self.text[..at];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .map_or(0, |line| line.chars().count())
+            + 1;
+        Err(Error::from_kind(ErrorKind::DomainSyntax(
+            line, column, message,
+        )))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+    fn skip_blanks(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+    fn next_is(&mut self, c: char) -> bool {
+        self.skip_blanks();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.next_is(c) {
+            Ok(())
+        } else {
+            let found = self.describe();
+            self.error(self.pos, format!("expected {:?}, found {}", c, found))
+        }
+    }
+    fn describe(&self) -> String {
+        match self.peek() {
+            Some(c) => format!("{:?}", c),
+            None => "end of domain".to_owned(),
+        }
+    }
+
+    fn value(&mut self) -> Result<Node> {
+        self.skip_blanks();
+        let at = self.pos;
+        let kind = match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                Kind::List(self.items(']')?.0)
+            }
+            Some('(') => {
+                self.pos += 1;
+                let (mut items, trailing_comma) = self.items(')')?;
+                // `(x)` is x, `(x,)` a tuple
+                if items.len() == 1 && !trailing_comma {
+                    return Ok(items.remove(0));
+                }
+                Kind::List(items)
+            }
+            Some('\'') | Some('"') => Kind::Scalar(Value::String(self.string(false)?)),
+            Some(c) if c == '-' || c == '+' || c == '.' || c.is_ascii_digit() => {
+                Kind::Scalar(self.number()?)
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => match self.expression()? {
+                Expr::Value(value) => Kind::Scalar(value),
+                Expr::Date(date) => Kind::Scalar(Value::from(date.format(DATE_FORMAT).to_string())),
+            },
+            _ => {
+                let found = self.describe();
+                return self.error(at, format!("expected a value, found {}", found));
+            }
+        };
+        Ok(Node { at, kind })
+    }
+
+    /// comma separated values up to `close`, and whether they end with a comma
+    fn items(&mut self, close: char) -> Result<(Vec<Node>, bool)> {
+        let mut items = Vec::new();
+        loop {
+            if self.next_is(close) {
+                return Ok((items, true));
+            }
+            items.push(self.value()?);
+            if self.next_is(close) {
+                return Ok((items, false));
+            }
+            if !self.next_is(',') {
+                let found = self.describe();
+                return self.error(
+                    self.pos,
+                    format!("expected ',' or {:?}, found {}", close, found),
+                );
+            }
+        }
+    }
+
+    fn string(&mut self, raw: bool) -> Result<String> {
+        let at = self.pos;
+        let quote = self.peek().unwrap_or('\'');
+        self.pos += 1;
+        let mut text = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    self.pos += offset + 1;
+                    return Ok(text);
+                }
+                '\n' => break,
+                '\\' if !raw => match chars.next() {
+                    Some((_, 'n')) => text.push('\n'),
+                    Some((_, 't')) => text.push('\t'),
+                    Some((_, 'r')) => text.push('\r'),
+                    Some((_, '0')) => text.push('\0'),
+                    Some((_, c)) if c == '\\' || c == '\'' || c == '"' => text.push(c),
+                    Some((start, c)) if c == 'x' || c == 'u' => {
+                        let len = if c == 'x' { 2 } else { 4 };
+                        let start = self.pos + start + 1;
+                        let code = self
+                            .text
+                            .get(start..start + len)
+                            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                            .and_then(std::char::from_u32);
+                        match code {
+                            Some(code) => text.push(code),
+                            None => {
+                                return self.error(start - 2, "invalid escape".to_owned());
+                            }
+                        }
+                        for _ in 0..len {
+                            chars.next();
+                        }
+                    }
+                    Some((_, c)) => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                    None => break,
+                },
+                c => text.push(c),
+            }
+        }
+        self.error(at, "unterminated string".to_owned())
+    }
+
+    fn number(&mut self) -> Result<Value> {
+        let at = self.pos;
+        let mut len = 0;
+        for (i, c) in self.text[at..].char_indices() {
+            let sign =
+                (c == '-' || c == '+') && (i == 0 || self.text[..at + i].ends_with(['e', 'E']));
+            if !(sign || c.is_ascii_digit() || c == '.' || c == '_' || c == 'e' || c == 'E') {
+                break;
+            }
+            len = i + 1;
+        }
+        let text = self.text[at..at + len].replace('_', "");
+        self.pos += len;
+        if let Ok(int) = text.parse::<i64>() {
+            return Ok(Value::from(int));
+        }
+        match text.parse::<f64>().ok().and_then(Number::from_f64) {
+            Some(float) => Ok(Value::Number(float)),
+            None => self.error(at, format!("invalid number {:?}", &self.text[at..at + len])),
+        }
+    }
+
+    fn identifier(&mut self) -> &'t str {
+        let start = self.pos;
+        let len = self.text[start..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(self.text.len() - start);
+        self.pos += len;
+        &self.text[start..start + len]
+    }
+
+    /// `True`, `uid`, `user.company_id.id`, `context_today().strftime(...)`
+    fn expression(&mut self) -> Result<Expr> {
+        let at = self.pos;
+        let mut name = self.identifier().to_owned();
+        if (name == "u" || name == "r") && matches!(self.peek(), Some('\'') | Some('"')) {
+            return Ok(Expr::Value(Value::String(self.string(name == "r")?)));
+        }
+        while self.peek() == Some('.') {
+            self.pos += 1;
+            let part = self.identifier();
+            if part.is_empty() {
+                let found = self.describe();
+                return self.error(self.pos, format!("expected a name, found {}", found));
+            }
+            name.push('.');
+            name.push_str(part);
+        }
+        let mut expr = if self.next_is('(') {
+            let (args, _) = self.items(')')?;
+            self.call(at, &name, None, &args)?
+        } else {
+            match name.as_str() {
+                "True" => Expr::Value(Value::Bool(true)),
+                "False" => Expr::Value(Value::Bool(false)),
+                "None" => Expr::Value(Value::Null),
+                _ => match self.placeholders.names.get(&name) {
+                    Some(value) => Expr::Value(value.to_owned()),
+                    None => return self.error(at, format!("unknown name {}", name)),
+                },
+            }
+        };
+        // methods of a call result
+        while self.peek() == Some('.') {
+            self.pos += 1;
+            let method_at = self.pos;
+            let method = self.identifier().to_owned();
+            self.expect('(')?;
+            let (args, _) = self.items(')')?;
+            expr = self.call(method_at, &method, Some(expr), &args)?;
+        }
+        Ok(expr)
+    }
+
+    fn call(&self, at: usize, name: &str, target: Option<Expr>, args: &[Node]) -> Result<Expr> {
+        match (name, target, args) {
+            ("context_today", None, []) => match self.placeholders.today {
+                Some(today) => Ok(Expr::Date(today)),
+                None => self.error(at, "context_today() needs a date placeholder".to_owned()),
+            },
+            ("time.strftime", None, [format]) => match self.placeholders.today {
+                Some(today) => self.strftime(today, format),
+                None => self.error(at, "time.strftime() needs a date placeholder".to_owned()),
+            },
+            ("strftime", Some(Expr::Date(date)), [format]) => self.strftime(date, format),
+            _ => self.error(at, format!("unsupported call {}()", name)),
+        }
+    }
+
+    fn strftime(&self, date: NaiveDate, node: &Node) -> Result<Expr> {
+        let format = match &node.kind {
+            Kind::Scalar(Value::String(format)) => format,
+            _ => return self.error(node.at, "expected a format string".to_owned()),
+        };
+        let mut text = String::new();
+        match write!(text, "{}", date.format(format)) {
+            Ok(()) => Ok(Expr::Value(Value::String(text))),
+            Err(_) => self.error(node.at, format!("unsupported date format {:?}", format)),
+        }
+    }
+
+    /// next expression of a prefix domain
+    fn term(&self, nodes: &mut slice::Iter<Node>, end: usize) -> Result<Domain> {
+        let node = match nodes.next() {
+            None => return self.error(end, "missing operand".to_owned()),
+            Some(node) => node,
+        };
+        match &node.kind {
+            Kind::Scalar(Value::String(op)) if op == "&" => {
+                let left = self.term(nodes, end)?;
+                Ok(Domain::And(vec![left, self.term(nodes, end)?]))
+            }
+            Kind::Scalar(Value::String(op)) if op == "|" => {
+                let left = self.term(nodes, end)?;
+                Ok(Domain::Or(vec![left, self.term(nodes, end)?]))
+            }
+            Kind::Scalar(Value::String(op)) if op == "!" => {
+                Ok(Domain::Not(Box::new(self.term(nodes, end)?)))
+            }
+            Kind::Scalar(Value::String(op)) => self.error(
+                node.at,
+                format!("unknown domain operator {:?}, expected '&', '|' or '!'", op),
+            ),
+            Kind::List(items) => self.leaf(node.at, items),
+            Kind::Scalar(_) => self.error(node.at, "expected a leaf or an operator".to_owned()),
+        }
+    }
+
+    fn leaf(&self, at: usize, items: &[Node]) -> Result<Domain> {
+        let (field, op, value) = match items {
+            [field, op, value] => (field, op, value),
+            _ => return self.error(at, "expected (field, operator, value)".to_owned()),
+        };
+        let op_name = match &op.kind {
+            Kind::Scalar(Value::String(op)) => op,
+            _ => return self.error(op.at, "expected an operator".to_owned()),
+        };
+        let operator = match op_name.parse::<Operator>() {
+            Ok(operator) => operator,
+            Err(_) => return self.error(op.at, format!("unknown operator {:?}", op_name)),
+        };
+        match (&field.kind, &value.kind) {
+            (Kind::Scalar(Value::String(field)), _) => Ok(Domain::Leaf {
+                field: field.to_owned(),
+                op: operator,
+                value: value.to_value(),
+            }),
+            (Kind::Scalar(Value::Number(left)), Kind::Scalar(Value::Number(right)))
+                if operator == Operator::Eq && right.as_u64() == Some(1) =>
+            {
+                match left.as_u64() {
+                    Some(1) => Ok(Domain::True),
+                    Some(0) => Ok(Domain::False),
+                    _ => self.error(field.at, "expected a field name".to_owned()),
+                }
+            }
+            _ => self.error(field.at, "expected a field name".to_owned()),
+        }
+    }
+
+    fn domain(&mut self) -> Result<Domain> {
+        let root = self.value()?;
+        self.skip_blanks();
+        if self.pos < self.text.len() {
+            let found = self.describe();
+            return self.error(self.pos, format!("expected end of domain, found {}", found));
+        }
+        let nodes = match &root.kind {
+            Kind::List(nodes) => nodes,
+            Kind::Scalar(_) => return self.error(root.at, "expected a list".to_owned()),
+        };
+        let end = self.text.trim_end().len().saturating_sub(1);
+        let mut terms = nodes.iter();
+        let mut domains = Vec::new();
+        while terms.len() > 0 {
+            domains.push(self.term(&mut terms, end)?);
+        }
+        Ok(match domains.len() {
+            0 => Domain::True,
+            1 => domains.remove(0),
+            _ => Domain::And(domains),
+        })
+    }
+}
+
+impl Domain {
+    /// parse a domain written as a Python literal, without placeholders
+    pub fn parse(text: &str) -> Result<Domain> {
+        Domain::parse_with(text, &Placeholders::new())
+    }
+    /// parse a domain written as a Python literal, substituting `placeholders`
+    pub fn parse_with(text: &str, placeholders: &Placeholders) -> Result<Domain> {
+        Parser {
+            text,
+            pos: 0,
+            placeholders,
+        }
+        .domain()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{allowed_company_ids, Placeholders};
+    use crate::{Domain, ErrorKind, SessionInfo};
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    fn position(text: &str) -> (usize, usize, String) {
+        match Domain::parse(text).unwrap_err().0 {
+            ErrorKind::DomainSyntax(line, column, message) => (line, column, message),
+            other => panic!("unexpected error {}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let domain = Domain::parse(
+            "['|', ('name', 'ilike', u\"l'abel\"), '!', ('qty', '>=', -1.5e2),
+              ('state', 'in', ('draft', 'done',)), ('product_id', '=', False), (1, '=', 1)]",
+        )
+        .unwrap();
+        assert_eq!(
+            domain.to_value(),
+            json!([
+                "&",
+                "&",
+                "&",
+                "|",
+                ["name", "ilike", "l'abel"],
+                "!",
+                ["qty", ">=", -150.0],
+                ["state", "in", ["draft", "done"]],
+                ["product_id", "=", false],
+                [1, "=", 1]
+            ])
+        );
+        assert_eq!(Domain::parse("  []  ").unwrap(), Domain::True);
+        assert_eq!(
+            Domain::parse("[('a', '=', (1)), ('b', '=', 'x\\ty\\u00e9')]")
+                .unwrap()
+                .to_value(),
+            json!(["&", ["a", "=", 1], ["b", "=", "x\ty\u{e9}"]])
+        );
+        assert_eq!(
+            Domain::parse("[('a', 'in', ((1,))), ('b', 'in', ((1, 2),))]")
+                .unwrap()
+                .to_value(),
+            json!(["&", ["a", "in", [1]], ["b", "in", [[1, 2]]]])
+        );
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let placeholders = Placeholders::new()
+            .with("uid", 2)
+            .with("user.company_ids.ids", [1, 3])
+            .with_today(NaiveDate::from_ymd_opt(2021, 3, 1).unwrap());
+        let domain = Domain::parse_with(
+            "[('user_id', '=', uid), ('company_id', 'in', user.company_ids.ids),
+              ('date', '<=', context_today().strftime('%Y-%m-%d')),
+              ('month', '=', time.strftime('%m/%Y'))]",
+            &placeholders,
+        )
+        .unwrap();
+        assert_eq!(
+            domain
+                .leaves()
+                .iter()
+                .map(|leaf| leaf.2)
+                .collect::<Vec<_>>(),
+            vec![
+                &json!(2),
+                &json!([1, 3]),
+                &json!("2021-03-01"),
+                &json!("03/2021")
+            ]
+        );
+        assert!(Domain::parse("[('user_id', '=', uid)]")
+            .unwrap_err()
+            .to_string()
+            .contains("unknown name uid"));
+    }
+
+    #[test]
+    fn test_allowed_company_ids() {
+        let session = |user_companies| -> SessionInfo {
+            serde_json::from_value(json!({
+                "company_id": 1, "db": "test", "partner_id": 3, "registered_contract": false,
+                "session_id": "x", "uid": 2, "username": "admin",
+                "user_context": {
+                    "current_week": false, "current_week2": false, "lang": "en_US", "tz": false
+                },
+                "user_companies": user_companies
+            }))
+            .unwrap()
+        };
+        assert_eq!(
+            allowed_company_ids(&session(json!({
+                "current_company": 1,
+                "allowed_companies": {"1": {"id": 1}, "3": {"id": 3}}
+            }))),
+            vec![1, 3]
+        );
+        assert_eq!(
+            allowed_company_ids(&session(json!({
+                "current_company": [1, "YourCompany"],
+                "allowed_companies": [[1, "YourCompany"], [4, "Branch"]]
+            }))),
+            vec![1, 4]
+        );
+        assert_eq!(allowed_company_ids(&session(json!(false))), vec![1]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            position("[('name', '=', 'x'),\n ('qty' '>', 3)]"),
+            (2, 9, "expected ',' or ')', found '\\''".to_owned())
+        );
+        assert_eq!(
+            position("[('name', '~', 'x')]"),
+            (1, 11, "unknown operator \"~\"".to_owned())
+        );
+        assert_eq!(
+            position("['|', ('a', '=', 1)]"),
+            (1, 20, "missing operand".to_owned())
+        );
+        assert_eq!(
+            position("[('a', '=')]"),
+            (1, 2, "expected (field, operator, value)".to_owned())
+        );
+        assert_eq!(
+            position("[('a', '=', 'x)]").2,
+            "unterminated string".to_owned()
+        );
+        assert_eq!(position("[('a', '=', 1)] extra").0, 1);
+        assert_eq!(
+            position("[('a', '=', foo.bar())]"),
+            (1, 13, "unsupported call foo.bar()".to_owned())
+        );
+    }
+}
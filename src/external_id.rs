@@ -0,0 +1,136 @@
+//! external ids (`ir.model.data`)
+//!
+//! ```ignore
+//! let admin = cli.env_ref("base.user_root")?;
+//! label.set_external_id("__import__.label_a0014")?;
+//! ```
+use serde_json::{json, Value};
+
+use crate::{Error, ErrorKind, OdooClient, RecordSet, Result};
+
+const IR_MODEL_DATA: &str = "ir.model.data";
+
+/// split `module.name`
+fn split_external_id(xml_id: &str) -> Result<(&str, &str)> {
+    match xml_id.find('.') {
+        Some(dot) if dot > 0 && dot + 1 < xml_id.len() => Ok((&xml_id[..dot], &xml_id[dot + 1..])),
+        _ => Err(Error::from_kind(ErrorKind::ExternalId(format!(
+            "{} is not of the form module.name",
+            xml_id
+        )))),
+    }
+}
+
+impl OdooClient {
+    /// `(model, res_id)` of external id `xml_id` (`module.name`)
+    ///
+    /// Lookups are cached for the session.
+    pub fn resolve_external_id(&self, xml_id: &str) -> Result<(String, u32)> {
+        if let Some(found) = self.refs.borrow().get(xml_id) {
+            return Ok(found.clone());
+        }
+        let (module, name) = split_external_id(xml_id)?;
+        let data = self.get_model(IR_MODEL_DATA)?;
+        let ids = data.search(json!([("module", "=", module), ("name", "=", name)]))?;
+        let rows = data.read(&ids, &["model", "res_id"])?;
+        let found = rows.first().and_then(|row| {
+            let model = row.get("model").and_then(|m| m.as_str())?;
+            let res_id = row.get("res_id").and_then(|id| id.as_u64())?;
+            Some((model.to_owned(), res_id as u32))
+        });
+        match found {
+            None => Err(Error::from_kind(ErrorKind::ExternalId(format!(
+                "{} not found",
+                xml_id
+            )))),
+            Some(found) => {
+                self.refs
+                    .borrow_mut()
+                    .insert(xml_id.to_owned(), found.clone());
+                Ok(found)
+            }
+        }
+    }
+    /// record referenced by external id `xml_id` (`module.name`)
+    pub fn env_ref(&self, xml_id: &str) -> Result<RecordSet<'_>> {
+        let (model, res_id) = self.resolve_external_id(xml_id)?;
+        let model = self.get_model(&model)?;
+        Ok(model.browse_lazy(&[res_id]))
+    }
+}
+
+impl RecordSet<'_> {
+    /// external ids of each record, in the order of the recordset
+    pub fn external_ids(&self) -> Result<Vec<(u32, Vec<String>)>> {
+        let cli = self.model.cli;
+        let data = cli.get_model(IR_MODEL_DATA)?;
+        let ids = data.search(json!([
+            ("model", "=", self.model.name()),
+            ("res_id", "in", &self.ids)
+        ]))?;
+        let rows = data.read(&ids, &["module", "name", "res_id"])?;
+        let mut found: Vec<(u32, Vec<String>)> =
+            self.ids.iter().map(|id| (*id, Vec::new())).collect();
+        let mut refs = cli.refs.borrow_mut();
+        for row in rows {
+            let field = |name: &str| row.get(name).cloned().unwrap_or(Value::Null);
+            let (module, name, res_id) = match (field("module"), field("name"), field("res_id")) {
+                (Value::String(module), Value::String(name), Value::Number(res_id)) => {
+                    (module, name, res_id.as_u64().unwrap_or(0) as u32)
+                }
+                _ => continue,
+            };
+            let xml_id = format!("{}.{}", module, name);
+            refs.insert(xml_id.clone(), (self.model.name().to_owned(), res_id));
+            if let Some((_, xml_ids)) = found.iter_mut().find(|(id, _)| *id == res_id) {
+                xml_ids.push(xml_id);
+            }
+        }
+        Ok(found)
+    }
+    /// attach external id `xml_id` (`module.name`) to the single record of this set
+    pub fn set_external_id(&self, xml_id: &str) -> Result<()> {
+        let (module, name) = split_external_id(xml_id)?;
+        let res_id = match self.ids.as_slice() {
+            [id] => *id,
+            _ => {
+                return Err(Error::from_kind(ErrorKind::ExternalId(format!(
+                    "{} expects a single record, got {:?}",
+                    xml_id, self
+                ))))
+            }
+        };
+        let cli = self.model.cli;
+        let data = cli.get_model(IR_MODEL_DATA)?;
+        data.create(json!({
+            "module": module,
+            "name": name,
+            "model": self.model.name(),
+            "res_id": res_id,
+        }))?;
+        cli.refs
+            .borrow_mut()
+            .insert(xml_id.to_owned(), (self.model.name().to_owned(), res_id));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_external_id;
+
+    #[test]
+    fn test_split_external_id() {
+        assert_eq!(
+            split_external_id("base.user_root").unwrap(),
+            ("base", "user_root")
+        );
+        assert_eq!(
+            split_external_id("__import__.label.a0014").unwrap(),
+            ("__import__", "label.a0014")
+        );
+        assert!(split_external_id("user_root").is_err());
+        assert!(split_external_id(".user_root").is_err());
+        assert!(split_external_id("base.").is_err());
+    }
+}
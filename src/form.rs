@@ -0,0 +1,205 @@
+//! record creation driven by `default_get` and `onchange`, like the web client
+//!
+//! ```ignore
+//! let mut form = stock_label.form()?;
+//! form.set("product_id", 42)?.set("name", "A0014")?;
+//! let label = form.save()?;
+//! ```
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{Error, ErrorKind, Model, RecordSet, Result};
+
+/// first server serie with the `onchange(values, field_names, fields_spec)` signature
+const ONCHANGE_SPEC_SERIE: u16 = 17;
+
+/// record being edited before its creation
+#[derive(Debug)]
+pub struct Form<'a> {
+    model: Model<'a>,
+    values: Map<String, Value>,
+    /// warnings returned by onchanges, in order
+    pub warnings: Vec<Value>,
+    /// domains returned by onchanges, by field (older servers only)
+    pub domains: Map<String, Value>,
+}
+
+/// convert a value read from the server to its write form
+///
+/// many2one `[id, name]` pairs and `{"id": id, ...}` dicts become ids.
+fn write_value(type_: &str, value: Value) -> Value {
+    match (type_, value) {
+        ("many2one", Value::Array(pair)) => pair.into_iter().next().unwrap_or(Value::Bool(false)),
+        ("many2one", Value::Object(rec)) => rec.get("id").cloned().unwrap_or(Value::Bool(false)),
+        (_, value) => value,
+    }
+}
+
+impl<'a> Form<'a> {
+    /// current value of field `name`
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+    /// current values of the form
+    pub fn values(&self) -> &Map<String, Value> {
+        &self.values
+    }
+    /// assign `value` to field `name` and apply the resulting onchange
+    pub fn set<T: Serialize>(&mut self, name: &str, value: T) -> Result<&mut Self> {
+        let type_ = match self.model.desc.fields.get(name) {
+            Some(desc) => desc.type_.clone(),
+            None => {
+                return Err(Error::from_kind(ErrorKind::InvalidField(format!(
+                    "{}.{}: no such field",
+                    self.model.desc.name, name
+                ))))
+            }
+        };
+        self.values
+            .insert(name.to_owned(), write_value(&type_, json!(value)));
+        match self.onchange(name) {
+            Err(err) => Err(err),
+            Ok(result) => {
+                self.apply(result);
+                Ok(self)
+            }
+        }
+    }
+    /// create the record from the current values
+    ///
+    /// Readonly fields are not sent, like the web client does.
+    pub fn save(self) -> Result<RecordSet<'a>> {
+        let fields = &self.model.desc.fields;
+        let vals: Map<String, Value> = self
+            .values
+            .into_iter()
+            .filter(|(name, _)| !matches!(fields.get(name), Some(desc) if desc.always_readonly()))
+            .collect();
+        let vals = Value::Object(vals);
+        match self.model.create(vals) {
+            Err(err) => Err(err),
+            Ok(id) => Ok(self.model.browse_lazy(&[id])),
+        }
+    }
+
+    fn onchange(&self, name: &str) -> Result<Value> {
+        let fields = self.model.desc.fields.iter();
+        let args = if self.model.cli.server_major() >= ONCHANGE_SPEC_SERIE {
+            let spec: Map<String, Value> = fields
+                .map(|(field, desc)| match desc.type_.as_str() {
+                    "many2one" | "one2many" | "many2many" => {
+                        (field.to_owned(), json!({"fields": {"display_name": {}}}))
+                    }
+                    _ => (field.to_owned(), json!({})),
+                })
+                .collect();
+            vec![json!([]), json!(self.values), json!([name]), json!(spec)]
+        } else {
+            let spec: Map<String, Value> = fields
+                .map(|(field, _)| (field.to_owned(), json!("1")))
+                .collect();
+            vec![json!([]), json!(self.values), json!(name), json!(spec)]
+        };
+        self.model.execute("onchange", args)
+    }
+
+    /// merge an onchange result into the form
+    fn apply(&mut self, result: Value) {
+        if let Some(Value::Object(values)) = result.get("value") {
+            for (name, value) in values {
+                let type_ = self
+                    .model
+                    .desc
+                    .fields
+                    .get(name)
+                    .map(|desc| desc.type_.as_str())
+                    .unwrap_or("");
+                self.values
+                    .insert(name.to_owned(), write_value(type_, value.to_owned()));
+            }
+        }
+        match result.get("warning") {
+            None | Some(Value::Bool(false)) | Some(Value::Null) => {}
+            Some(warning) => self.warnings.push(warning.to_owned()),
+        }
+        if let Some(Value::Object(domains)) = result.get("domain") {
+            for (name, domain) in domains {
+                self.domains.insert(name.to_owned(), domain.to_owned());
+            }
+        }
+    }
+}
+
+impl<'a> Model<'a> {
+    /// default values for fields `names`
+    pub fn default_get(&self, names: &[&str]) -> Result<Map<String, Value>> {
+        match self.execute("default_get", vec![json!(names)]) {
+            Err(err) => Err(err),
+            Ok(Value::Object(defaults)) => Ok(defaults),
+            Ok(other) => Err(Error::from_kind(ErrorKind::MyOtherError(format!(
+                "unexpected default_get result: {}",
+                other
+            )))),
+        }
+    }
+    /// start a new record from the model defaults
+    pub fn form(&self) -> Result<Form<'a>> {
+        let names: Vec<&str> = self.desc.fields.keys().map(|name| name.as_str()).collect();
+        match self.default_get(&names) {
+            Err(err) => Err(err),
+            Ok(defaults) => {
+                let mut form = Form {
+                    model: self.clone(),
+                    values: Map::new(),
+                    warnings: vec![],
+                    domains: Map::new(),
+                };
+                form.apply(json!({ "value": defaults }));
+                Ok(form)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Form;
+    use crate::tests::{offline_client, offline_model};
+    use serde_json::{json, Map};
+
+    #[test]
+    fn test_apply_onchange() {
+        let cli = offline_client();
+        let model = offline_model(
+            &cli,
+            "stock.label",
+            &[
+                ("name", "char"),
+                ("product_id", "many2one"),
+                ("location_id", "many2one"),
+            ],
+        );
+        let mut form = Form {
+            model,
+            values: Map::new(),
+            warnings: vec![],
+            domains: Map::new(),
+        };
+        form.apply(json!({
+            "value": {"product_id": [3, "Handset"], "location_id": {"id": 8, "display_name": "Stock"}},
+            "warning": {"title": "Careful", "message": "serial already used"},
+            "domain": {"location_id": [["usage", "=", "internal"]]}
+        }));
+        assert_eq!(form.get("product_id"), Some(&json!(3)));
+        assert_eq!(form.get("location_id"), Some(&json!(8)));
+        assert_eq!(form.warnings.len(), 1);
+        assert_eq!(
+            form.domains["location_id"],
+            json!([["usage", "=", "internal"]])
+        );
+
+        form.apply(json!({"value": {"name": "A0014"}, "warning": false}));
+        assert_eq!(form.get("name"), Some(&json!("A0014")));
+        assert_eq!(form.warnings.len(), 1);
+    }
+}
@@ -0,0 +1,365 @@
+//! `read_group` support
+//!
+//! Older servers expose `read_group`, newer ones (19+) `formatted_read_group`,
+//! both answers are decoded into the same [`Group`] rows.
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use crate::{Error, ErrorKind, IntoDomain, Model, ObjectDescriptor, Result};
+
+/// first server serie exposing `formatted_read_group`
+const FORMATTED_READ_GROUP_SERIE: u16 = 19;
+
+/// value of a group key
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupKey {
+    /// records where the field is not set (`false`)
+    Empty,
+    /// plain scalar value (char, selection, boolean, ...)
+    Value(Value),
+    /// many2one key as `(id, display_name)`
+    Many2one(u32, String),
+    /// date/datetime key grouped by granularity (`create_date:month`)
+    Period {
+        label: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+/// one row of a `read_group` answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    /// group keys, by groupby spec (`state`, `create_date:month`)
+    pub keys: BTreeMap<String, GroupKey>,
+    /// aggregated values, by field name
+    pub aggregates: BTreeMap<String, Value>,
+    /// number of records in the group (`__count`)
+    pub count: u64,
+    /// domain selecting the records of the group (`__domain`)
+    pub domain: Value,
+}
+
+fn field_name(spec: &str) -> &str {
+    spec.split(':').next().unwrap_or(spec)
+}
+
+/// `formatted_read_group` aggregates for `fields`, always with `__count`
+///
+/// A field given without aggregator gets the one of its descriptor
+/// (`aggregator`, `group_operator` before 17.0). Fields without any are
+/// dropped, the server rejects them where `read_group` ignored them.
+fn formatted_aggregates(desc: &ObjectDescriptor, fields: &[&str], groupby: &[&str]) -> Vec<String> {
+    let mut aggregates: Vec<String> = fields
+        .iter()
+        .filter(|spec| **spec != "__count")
+        .filter(|spec| !groupby.iter().any(|g| field_name(g) == field_name(spec)))
+        .filter_map(|spec| {
+            if spec.contains(':') {
+                return Some(spec.to_string());
+            }
+            let field = desc.fields.get(*spec)?;
+            let aggregator = field
+                .extra
+                .get("aggregator")
+                .or_else(|| field.extra.get("group_operator"))
+                .and_then(|aggregator| aggregator.as_str())?;
+            Some(format!("{}:{}", spec, aggregator))
+        })
+        .collect();
+    aggregates.push("__count".to_owned());
+    aggregates
+}
+
+fn decode_key(spec: &str, value: Option<&Value>, range: Option<&Value>) -> GroupKey {
+    let granular = spec.contains(':');
+    match value {
+        None | Some(Value::Bool(false)) | Some(Value::Null) => GroupKey::Empty,
+        Some(Value::Array(pair)) if pair.len() == 2 => match (&pair[0], &pair[1]) {
+            (Value::String(from), Value::String(label)) if granular => GroupKey::Period {
+                label: label.to_owned(),
+                from: Some(from.to_owned()),
+                to: None,
+            },
+            (Value::Number(id), Value::String(name)) => match id.as_u64() {
+                Some(id) => GroupKey::Many2one(id as u32, name.to_owned()),
+                None => GroupKey::Value(Value::Array(pair.to_owned())),
+            },
+            _ => GroupKey::Value(Value::Array(pair.to_owned())),
+        },
+        Some(Value::String(label)) if granular => {
+            let bound = |name: &str| {
+                range
+                    .and_then(|r| r.get(spec))
+                    .and_then(|r| r.get(name))
+                    .and_then(|v| v.as_str())
+                    .map(|v| v.to_owned())
+            };
+            GroupKey::Period {
+                label: label.to_owned(),
+                from: bound("from"),
+                to: bound("to"),
+            }
+        }
+        Some(value) => GroupKey::Value(value.to_owned()),
+    }
+}
+
+impl Group {
+    /// decode a row returned by `read_group`
+    pub fn from_read_group(row: &Map<String, Value>, fields: &[&str], groupby: &[&str]) -> Group {
+        let keys = groupby
+            .iter()
+            .filter(|spec| row.contains_key(**spec))
+            .map(|spec| {
+                (
+                    spec.to_string(),
+                    decode_key(spec, row.get(*spec), row.get("__range")),
+                )
+            })
+            .collect();
+        let aggregates = fields
+            .iter()
+            .map(|spec| field_name(spec))
+            .filter(|name| !groupby.iter().any(|g| field_name(g) == *name))
+            .filter_map(|name| row.get(name).map(|v| (name.to_owned(), v.to_owned())))
+            .collect();
+        let count = row
+            .get("__count")
+            .or_else(|| {
+                groupby
+                    .first()
+                    .and_then(|g| row.get(&format!("{}_count", field_name(g))))
+            })
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        Group {
+            keys,
+            aggregates,
+            count,
+            domain: row.get("__domain").cloned().unwrap_or_else(|| json!([])),
+        }
+    }
+
+    /// decode a row returned by `formatted_read_group`
+    ///
+    /// `__extra_domain` is relative to the searched `domain`, both are
+    /// and-ed together so that `Group::domain` can be used on its own.
+    pub fn from_formatted_read_group(
+        row: &Map<String, Value>,
+        aggregates: &[String],
+        groupby: &[&str],
+        domain: &Value,
+    ) -> Group {
+        let keys = groupby
+            .iter()
+            .map(|spec| (spec.to_string(), decode_key(spec, row.get(*spec), None)))
+            .collect();
+        let aggregates = aggregates
+            .iter()
+            .filter(|spec| *spec != "__count")
+            .filter_map(|spec| {
+                row.get(spec.as_str())
+                    .map(|v| (field_name(spec).to_owned(), v.to_owned()))
+            })
+            .collect();
+        let mut leaves = domain.as_array().cloned().unwrap_or_default();
+        if let Some(Value::Array(extra)) = row.get("__extra_domain") {
+            leaves.extend(extra.iter().cloned());
+        }
+        Group {
+            keys,
+            aggregates,
+            count: row.get("__count").and_then(|v| v.as_u64()).unwrap_or(0),
+            domain: Value::Array(leaves),
+        }
+    }
+}
+
+impl Model<'_> {
+    /// aggregate records matching `domain`, grouped by `groupby`
+    ///
+    /// `fields` lists the aggregates as `field` or `field:aggregator`
+    /// (`amount:sum`, `id:count_distinct`), fields without aggregator are
+    /// ignored. `groupby` may use date granularities (`create_date:month`).
+    /// With `lazy`, only the first groupby is applied, like the web client
+    /// does.
+    pub fn read_group<D: IntoDomain>(
+        &self,
+        domain: D,
+        fields: &[&str],
+        groupby: &[&str],
+        lazy: bool,
+        orderby: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<Vec<Group>> {
+        let domain = domain.into_domain()?;
+        self.check_domain(&domain)?;
+        let domain = domain.to_value();
+        let groupby = if lazy && groupby.len() > 1 {
+            &groupby[..1]
+        } else {
+            groupby
+        };
+        let formatted = self.cli.server_major() >= FORMATTED_READ_GROUP_SERIE;
+        let rows = if formatted {
+            let aggregates = formatted_aggregates(&self.desc, fields, groupby);
+            self.execute(
+                "formatted_read_group",
+                vec![
+                    domain.clone(),
+                    json!(groupby),
+                    json!(aggregates),
+                    json!([]),
+                    json!(0),
+                    json!(limit),
+                    json!(orderby),
+                ],
+            )
+            .map(|rows| (rows, Some(aggregates)))
+        } else {
+            self.execute(
+                "read_group",
+                vec![
+                    domain.clone(),
+                    json!(fields),
+                    json!(groupby),
+                    json!(0),
+                    json!(limit),
+                    json!(orderby.unwrap_or("")),
+                    json!(lazy),
+                ],
+            )
+            .map(|rows| (rows, None))
+        };
+        match rows {
+            Err(err) => Err(err),
+            Ok((Value::Array(rows), aggregates)) => Ok(rows
+                .iter()
+                .filter_map(|row| row.as_object())
+                .map(|row| match &aggregates {
+                    Some(aggregates) => {
+                        Group::from_formatted_read_group(row, aggregates, groupby, &domain)
+                    }
+                    None => Group::from_read_group(row, fields, groupby),
+                })
+                .collect()),
+            Ok((other, _)) => Err(Error::from_kind(ErrorKind::MyOtherError(format!(
+                "unexpected read_group result: {}",
+                other
+            )))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{formatted_aggregates, Group, GroupKey};
+    use crate::{FieldDescriptor, ObjectDescriptor};
+    use serde_json::json;
+
+    #[test]
+    fn test_read_group_row() {
+        let row = json!({
+            "product_id": [3, "Handset"],
+            "create_date:month": "February 2021",
+            "__range": {"create_date:month": {"from": "2021-02-01", "to": "2021-03-01"}},
+            "qty": 12.0,
+            "__count": 4,
+            "__domain": [["product_id", "=", 3]]
+        });
+        let group = Group::from_read_group(
+            row.as_object().unwrap(),
+            &["qty:sum"],
+            &["product_id", "create_date:month"],
+        );
+        assert_eq!(
+            group.keys["product_id"],
+            GroupKey::Many2one(3, "Handset".to_owned())
+        );
+        assert_eq!(
+            group.keys["create_date:month"],
+            GroupKey::Period {
+                label: "February 2021".to_owned(),
+                from: Some("2021-02-01".to_owned()),
+                to: Some("2021-03-01".to_owned()),
+            }
+        );
+        assert_eq!(group.aggregates["qty"], json!(12.0));
+        assert_eq!(group.count, 4);
+        assert_eq!(group.domain, json!([["product_id", "=", 3]]));
+    }
+
+    #[test]
+    fn test_read_group_lazy_count() {
+        let row = json!({"state": false, "state_count": 2, "__domain": []});
+        let group = Group::from_read_group(row.as_object().unwrap(), &[], &["state"]);
+        assert_eq!(group.keys["state"], GroupKey::Empty);
+        assert_eq!(group.count, 2);
+    }
+
+    #[test]
+    fn test_formatted_read_group_row() {
+        let row = json!({
+            "state": "draft",
+            "qty:sum": 3.0,
+            "__count": 1,
+            "__extra_domain": [["state", "=", "draft"]]
+        });
+        let group = Group::from_formatted_read_group(
+            row.as_object().unwrap(),
+            &["qty:sum".to_owned()],
+            &["state"],
+            &json!([["is_terminal", "=", true]]),
+        );
+        assert_eq!(group.keys["state"], GroupKey::Value(json!("draft")));
+        assert_eq!(group.aggregates["qty"], json!(3.0));
+        assert_eq!(group.count, 1);
+        assert_eq!(
+            group.domain,
+            json!([["is_terminal", "=", true], ["state", "=", "draft"]])
+        );
+    }
+
+    #[test]
+    fn test_formatted_aggregates() {
+        let field = |type_: &str, aggregator: Option<&str>| FieldDescriptor {
+            type_: type_.to_owned(),
+            extra: aggregator
+                .map(|aggregator| ("aggregator".to_owned(), json!(aggregator)))
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let desc = ObjectDescriptor {
+            name: "stock.label".to_owned(),
+            fields: vec![
+                ("qty".to_owned(), field("float", Some("sum"))),
+                ("weight".to_owned(), field("float", Some("avg"))),
+                ("name".to_owned(), field("char", None)),
+                ("state".to_owned(), field("selection", None)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let aggregates = formatted_aggregates(
+            &desc,
+            &["qty", "weight", "name", "id:count_distinct", "state"],
+            &["state"],
+        );
+        assert_eq!(
+            aggregates,
+            vec!["qty:sum", "weight:avg", "id:count_distinct", "__count"]
+        );
+
+        let row = json!({"state": "done", "qty:sum": 5.0, "__count": 7});
+        let group = Group::from_formatted_read_group(
+            row.as_object().unwrap(),
+            &aggregates,
+            &["state"],
+            &json!([]),
+        );
+        assert_eq!(group.count, 7);
+        assert!(!group.aggregates.contains_key("__count"));
+    }
+}
@@ -0,0 +1,94 @@
+//! lenient deserializers for `fields_get` attributes
+//!
+//! Attribute types vary across server versions (`readonly` may be `0`/`1`,
+//! unset attributes are often `false`), a descriptor must never be lost
+//! because of such differences.
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !matches!(s.as_str(), "" | "0" | "False" | "false"),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+/// any value, with Python truthiness
+pub fn bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Value::deserialize(deserializer).map(|value| truthy(&value))
+}
+
+/// string, `false`/`null` being empty
+pub fn string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(s) => s,
+        Value::Null | Value::Bool(false) => String::new(),
+        other => other.to_string(),
+    })
+}
+
+/// optional string, `false`/`null`/`""` being `None`
+pub fn opt_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    string(deserializer).map(|s| if s.is_empty() { None } else { Some(s) })
+}
+
+/// optional number, `false`/`null` being `None`
+pub fn opt_u32<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(n) => n.as_u64().map(|n| n as u32),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    })
+}
+
+/// list of strings, a single string being a one element list
+pub fn strings<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => s,
+                other => other.to_string(),
+            })
+            .collect(),
+        Value::String(s) => vec![s],
+        _ => vec![],
+    })
+}
+
+/// `(precision, scale)`, `false`/`null` being `None`
+pub fn digits<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<(u32, u32)>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Array(pair) => match (
+            pair.first().and_then(|p| p.as_u64()),
+            pair.get(1).and_then(|s| s.as_u64()),
+        ) {
+            (Some(precision), Some(scale)) => Some((precision as u32, scale as u32)),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// `[(key, label)]`, non string keys (integers on some models) are stringified
+pub fn selection<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(String, String)>, D::Error> {
+    let text = |value: Option<&Value>| match value {
+        Some(Value::String(s)) => s.to_owned(),
+        Some(Value::Null) | Some(Value::Bool(false)) | None => String::new(),
+        Some(other) => other.to_string(),
+    };
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| item.as_array())
+            .map(|pair| (text(pair.first()), text(pair.get(1))))
+            .collect(),
+        _ => vec![],
+    })
+}
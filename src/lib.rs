@@ -1,4 +1,6 @@
-use serde::{Deserialize, Serialize};
+// error_chain errors are large, and its macro checks a cfg unknown to recent compilers
+#![allow(clippy::result_large_err, unexpected_cfgs)]
+use ::serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_json::Map;
 use serde_json::Value;
@@ -12,7 +14,9 @@ use std::io::prelude::*;
 use std::io::BufWriter;
 use std::io::Cursor;
 
+use std::cell::RefCell;
 use std::collections::BTreeMap;
+use std::rc::Rc;
 
 use log::{debug, info};
 use reqwest::blocking::Client;
@@ -25,6 +29,45 @@ lazy_static! {
 }
 
 
+pub mod binary;
+pub mod cache;
+pub mod codegen;
+pub mod command;
+pub mod datetime;
+pub mod domain;
+pub mod domain_parser;
+pub mod domain_check;
+pub mod domain_eval;
+#[cfg(feature = "decimal")]
+pub mod decimal;
+pub mod external_id;
+pub mod form;
+pub mod group;
+mod lenient;
+pub mod record;
+pub mod selection;
+pub mod serde;
+pub mod typed;
+pub mod validate;
+pub mod value;
+
+pub use cache::{CacheMetrics, CachePolicy, RecordCache};
+pub use chrono;
+pub use command::Command;
+pub use domain::{Domain, IntoDomain, Operator};
+pub use form::Form;
+pub use group::{Group, GroupKey};
+pub use record::{Record, Records};
+pub use roudoudou_derive::{OdooModel, OdooSelection};
+pub use selection::Selection;
+pub use serde_json;
+pub use typed::{Many2one, OdooModel, TypedModel, X2many};
+pub use validate::{ValidationMode, Violation};
+pub use value::OdooValue;
+
+// lets `#[derive(OdooModel)]` refer to `::roudoudou` within this crate
+extern crate self as roudoudou;
+
 #[macro_use]
 extern crate error_chain;
 error_chain! {
@@ -45,6 +88,42 @@ error_chain! {
             description("odoo client must be connected")
             display("not connected")
         }
+        InvalidField(t: String) {
+            description("invalid field")
+            display("invalid field: {}", t)
+        }
+        ModelMismatch(left: String, right: String) {
+            description("recordsets of different models")
+            display("recordsets of different models: {} and {}", left, right)
+        }
+        ExternalId(t: String) {
+            description("external id error")
+            display("external id: {}", t)
+        }
+        SchemaMismatch(model: String, problems: Vec<String>) {
+            description("struct does not match the server model")
+            display("{} does not match the server: {}", model, problems.join("; "))
+        }
+        InvalidDomain(t: String) {
+            description("invalid domain")
+            display("invalid domain: {}", t)
+        }
+        DomainSyntax(line: usize, column: usize, message: String) {
+            description("invalid domain string")
+            display("invalid domain at line {}, column {}: {}", line, column, message)
+        }
+        DomainMismatch(model: String, problems: Vec<String>) {
+            description("domain does not match the model fields")
+            display("invalid domain for {}: {}", model, problems.join("; "))
+        }
+        Validation(model: String, violations: Vec<Violation>) {
+            description("invalid create/write values")
+            display("invalid values for {}: {}", model, violations
+                .iter()
+                .map(|violation| violation.to_string())
+                .collect::<Vec<_>>()
+                .join("; "))
+        }
     }
     foreign_links {
         ParseError(ParseError);
@@ -67,6 +146,23 @@ pub struct VersionInfo {
     server_version_info: Option<(u16, u16, u16, String, u16, String)>,
 }
 
+impl VersionInfo {
+    /// major version of the server (`9` for `"9.0"`, `17` for `"saas~17.2"`)
+    pub fn major(&self) -> Option<u16> {
+        if let Some(info) = &self.server_version_info {
+            return Some(info.0);
+        }
+        match &self.server_serial {
+            OString::Filled(serie) => serie
+                .trim_start_matches("saas~")
+                .split('.')
+                .next()
+                .and_then(|major| major.parse().ok()),
+            OString::Absent(_) => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserContext {
     current_week: OString,
@@ -84,23 +180,106 @@ pub struct SessionInfo {
     pub uid: u32,
     pub user_context: UserContext,
     pub username: String,
+    /// current and allowed companies of the user (13.0+)
+    #[serde(default)]
+    pub user_companies: Value,
 }
 /// raw Odoo field descriptor
+///
+/// Parsing is lenient: missing attributes take their default value and
+/// attributes not listed here are kept in `extra`.
 #[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FieldDescriptor {
+    #[serde(deserialize_with = "lenient::bool")]
     pub change_default: bool,
+    #[serde(deserialize_with = "lenient::bool")]
     pub company_dependent: bool,
+    #[serde(deserialize_with = "lenient::strings")]
     pub depends: Vec<String>,
     pub help: Value,
+    #[serde(deserialize_with = "lenient::bool")]
     pub manual: bool,
+    #[serde(deserialize_with = "lenient::bool")]
     pub readonly: bool,
+    #[serde(deserialize_with = "lenient::bool")]
     pub required: bool,
+    #[serde(deserialize_with = "lenient::bool")]
     pub searchable: bool,
+    #[serde(deserialize_with = "lenient::bool")]
     pub sortable: bool,
+    #[serde(deserialize_with = "lenient::bool")]
     pub store: bool,
+    #[serde(deserialize_with = "lenient::string")]
     pub string: String,
-    #[serde(rename = "type")]
+    #[serde(rename = "type", deserialize_with = "lenient::string")]
     pub type_: String,
+    /// comodel of relational fields
+    #[serde(deserialize_with = "lenient::opt_string")]
+    pub relation: Option<String>,
+    /// inverse many2one of one2many fields
+    #[serde(deserialize_with = "lenient::opt_string")]
+    pub relation_field: Option<String>,
+    /// `(key, label)` of selection fields
+    #[serde(deserialize_with = "lenient::selection")]
+    pub selection: Vec<(String, String)>,
+    /// `(precision, scale)` of float and monetary fields
+    #[serde(deserialize_with = "lenient::digits")]
+    pub digits: Option<(u32, u32)>,
+    /// maximum length of char fields
+    #[serde(deserialize_with = "lenient::opt_u32")]
+    pub size: Option<u32>,
+    /// domain of relational fields (a list, or a string to evaluate)
+    pub domain: Value,
+    /// context of relational fields (an object, or a string to evaluate)
+    pub context: Value,
+    #[serde(deserialize_with = "lenient::bool")]
+    pub translate: bool,
+    /// comma separated xml ids of the groups allowed to access the field
+    #[serde(deserialize_with = "lenient::opt_string")]
+    pub groups: Option<String>,
+    /// attributes changed by record state (before 17.0)
+    pub states: Value,
+    /// currency field of monetary fields
+    #[serde(deserialize_with = "lenient::opt_string")]
+    pub currency_field: Option<String>,
+    /// any other attribute
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+impl FieldDescriptor {
+    /// parse a descriptor returned by `fields_get`
+    ///
+    /// Never fails on an object: if the attributes can not be decoded, the
+    /// type and label are kept and everything else goes to `extra`.
+    pub fn from_value(value: &Value) -> Option<FieldDescriptor> {
+        let attrs = value.as_object()?;
+        match serde_json::from_value(value.to_owned()) {
+            Ok(desc) => Some(desc),
+            Err(err) => {
+                log::warn!("partial field descriptor {}: {}", value, err);
+                let text = |name: &str| {
+                    attrs
+                        .get(name)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_owned()
+                };
+                Some(FieldDescriptor {
+                    type_: text("type"),
+                    string: text("string"),
+                    extra: attrs.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+    /// is this field loaded by default when browsing records?
+    ///
+    /// Only cheap stored fields are: binary and x2many fields are not.
+    pub fn is_prefetched(&self) -> bool {
+        self.store && !matches!(self.type_.as_str(), "binary" | "one2many" | "many2many")
+    }
 }
 /// raw Odoo object descriptor
 #[derive(Debug, Serialize, Deserialize)]
@@ -123,24 +302,21 @@ impl ObjectDescriptor {
         self.fields
             .iter()
             .filter(|(_, desc)| desc.required)
-            .map(|(name, desc)| (name, desc))
             .collect()
     }
     pub fn get_relational_fields(&self) -> Vec<(&String, &FieldDescriptor)> {
         self.fields
             .iter()
-            .filter(|(_, desc)| match desc.type_.as_str() {
-                "one2many" | "many2one" | "many2many" => true,
-                _ => false,
+            .filter(|(_, desc)| {
+                matches!(desc.type_.as_str(), "one2many" | "many2one" | "many2many")
             })
             .collect()
     }
     pub fn get_scalar_fields(&self) -> Vec<(&String, &FieldDescriptor)> {
         self.fields
             .iter()
-            .filter(|(_, desc)| match desc.type_.as_str() {
-                "one2many" | "many2one" | "many2many" => false,
-                _ => true,
+            .filter(|(_, desc)| {
+                !matches!(desc.type_.as_str(), "one2many" | "many2one" | "many2many")
             })
             .collect()
     }
@@ -166,12 +342,14 @@ pub struct RpcRequest<'a> {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct RpcResponse {
     jsonrpc: String,
     id: u32,
     result: Value,
 }
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct RpcError {
     jsonrpc: String,
     id: u32,
@@ -211,12 +389,21 @@ const ODOO_JSONRPC: &str = "/jsonrpc";
 
 const JSONRPC_20: &str = "2.0";
 
+/// first server serie where `name_get` is gone in favor of `display_name`
+const DISPLAY_NAME_SERIE: u16 = 17;
+
 #[derive(Debug)]
 pub struct OdooRpc {
     pub base_url: Url,
     http: Client,
 }
 
+impl Default for OdooRpc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl OdooRpc {
     pub fn new() -> Self {
         OdooRpc {
@@ -227,9 +414,9 @@ impl OdooRpc {
     pub fn encode_query<'a>(&self, method: &'a str, params: Value) -> RpcRequest<'a> {
         RpcRequest {
             jsonrpc: JSONRPC_20,
-            method: method,
+            method,
             id: 1,
-            params: params,
+            params,
         }
     }
     pub fn send_payload(&self, endpoint: &str, payload: RpcRequest) -> Result<Response> {
@@ -265,7 +452,7 @@ impl OdooRpc {
                                     Err(Error::from(ErrorKind::JsonError(err)))
                                 }
                             }
-                        } else if let Some(_) = j.get("error") {
+                        } else if j.get("error").is_some() {
                             let rcp_err = serde_json::from_value::<RpcError>(j).unwrap();
                             let res = rcp_err.error;
 
@@ -297,6 +484,16 @@ pub struct OdooApi {
 pub struct OdooClient {
     pub api: OdooApi,
     session: Option<SessionInfo>,
+    version: Option<VersionInfo>,
+    models: RefCell<BTreeMap<String, Rc<ObjectDescriptor>>>,
+    refs: RefCell<BTreeMap<String, (String, u32)>>,
+    cache: RefCell<RecordCache>,
+}
+
+impl Default for OdooClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl OdooClient {
@@ -305,14 +502,14 @@ impl OdooClient {
         OdooClient {
             api: OdooApi::new(rpc),
             session: None,
+            version: None,
+            models: RefCell::new(BTreeMap::new()),
+            refs: RefCell::new(BTreeMap::new()),
+            cache: RefCell::new(RecordCache::default()),
         }
     }
     pub fn is_connected(&self) -> bool {
-        if let Some(_) = self.session {
-            true
-        } else {
-            false
-        }
+        self.session.is_some()
     }
     pub fn login(&mut self, db: &str, user: &str, password: &str) -> Result<&mut Self> {
         if self.is_connected() {
@@ -325,6 +522,10 @@ impl OdooClient {
             Err(e) => Err(e),
             Ok(session) => {
                 self.session = Some(session);
+                self.version = self.api.version_info().ok();
+                self.models.borrow_mut().clear();
+                self.refs.borrow_mut().clear();
+                self.cache.borrow_mut().clear();
                 Ok(self)
             }
         }
@@ -344,17 +545,47 @@ impl OdooClient {
             }
         }
     }
-    pub fn get_model(&self, name: &str) -> Result<Model> {
+    /// version of the server we are logged in, if known
+    pub fn server_version(&self) -> Option<&VersionInfo> {
+        self.version.as_ref()
+    }
+    /// major version of the server, defaults to 0 if unknown
+    pub fn server_major(&self) -> u16 {
+        self.version.as_ref().and_then(|v| v.major()).unwrap_or(0)
+    }
+    /// change the record cache policy (`CachePolicy::Session` by default)
+    pub fn set_cache_policy(&self, policy: CachePolicy) {
+        self.cache.borrow_mut().set_policy(policy);
+    }
+    /// record cache hits, misses and invalidations
+    pub fn cache_metrics(&self) -> CacheMetrics {
+        self.cache.borrow().metrics()
+    }
+    /// drop every record cached by this client
+    pub fn invalidate_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+    /// get model `name`, its descriptor is fetched once per session
+    pub fn get_model(&self, name: &str) -> Result<Model<'_>> {
         match &self.session {
             None => Err(Error::from_kind(ErrorKind::ClientState(
                 "not connected".to_owned(),
             ))),
             Some(session) => {
+                if let Some(desc) = self.models.borrow().get(name) {
+                    return Ok(Model::new(Rc::clone(desc), self));
+                }
                 match self
                     .api
                     .object_fields_get(&session.db, session.uid, &session.username, name)
                 {
-                    Ok(desc) => Ok(Model { desc, cli: self }),
+                    Ok(desc) => {
+                        let desc = Rc::new(desc);
+                        self.models
+                            .borrow_mut()
+                            .insert(name.to_owned(), Rc::clone(&desc));
+                        Ok(Model::new(desc, self))
+                    }
                     Err(err) => Err(err),
                 }
             }
@@ -362,9 +593,12 @@ impl OdooClient {
     }
 }
 /// Odoo Model object
+#[derive(Clone)]
 pub struct Model<'a> {
-    desc: ObjectDescriptor,
+    desc: Rc<ObjectDescriptor>,
     cli: &'a OdooClient,
+    prefetch_groups: Vec<Vec<String>>,
+    check_domains: bool,
 }
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum MethodKind {
@@ -391,18 +625,66 @@ impl fmt::Debug for Model<'_> {
     }
 }
 
-impl Model<'_> {
-    pub fn call(&self, method: &str, args: Option<Value>, kwargs: Option<Value>) -> Result<Value> {
+impl<'a> Model<'a> {
+    fn new(desc: Rc<ObjectDescriptor>, cli: &'a OdooClient) -> Self {
+        Model {
+            desc,
+            cli,
+            prefetch_groups: vec![],
+            check_domains: false,
+        }
+    }
+    /// model name
+    pub fn name(&self) -> &str {
+        &self.desc.name
+    }
+    /// model descriptor, as returned by `fields_get`
+    pub fn descriptor(&self) -> &ObjectDescriptor {
+        &self.desc
+    }
+    /// drop cached values of this model, restricted to fields `names` and
+    /// records `ids` if given, like the server `invalidate_cache`
+    pub fn invalidate_cache(&self, names: Option<&[&str]>, ids: Option<&[u32]>) {
+        self.cli
+            .cache
+            .borrow_mut()
+            .invalidate(&self.desc.name, names, ids);
+    }
+    /// call model method `method`
+    ///
+    /// `args` are the positional arguments, see [`Args`], `kwargs` an object
+    /// of keyword arguments. Cached records of the model are invalidated like
+    /// with [`Model::execute`].
+    pub fn call<A: Into<Args>>(
+        &self,
+        method: &str,
+        args: A,
+        kwargs: Option<Value>,
+    ) -> Result<Value> {
+        match keywords(kwargs) {
+            Err(err) => Err(err),
+            Ok(kwargs) => self.call_kw(method, args.into().0, kwargs),
+        }
+    }
+    /// call model method `method` with positional `args` and keyword `kwargs`
+    pub fn call_kw(
+        &self,
+        method: &str,
+        args: Vec<Value>,
+        kwargs: Map<String, Value>,
+    ) -> Result<Value> {
+        if cache::invalidates(method) {
+            self.invalidate_cache(None, None);
+        }
         match &self.cli.session {
             None => Err(Error::from_kind(ErrorKind::ClientState(
                 "not connected".to_owned(),
             ))),
-            Some(session) => self.cli.api.recordset_call(
+            Some(session) => self.cli.api.call_kw(
                 &session.db,
                 1,
                 "admin",
                 &self.desc.name,
-                None,
                 method,
                 args,
                 kwargs,
@@ -411,37 +693,213 @@ impl Model<'_> {
     }
 }
 /// Odoo RecordSet
+///
+/// Field values are loaded on demand, one read for the whole set.
 pub struct RecordSet<'a> {
     pub ids: Vec<u32>,
-    pub model: &'a Model<'a>,
-    pub data: Vec<Value>,
+    pub model: Model<'a>,
+    data: RefCell<BTreeMap<u32, Map<String, Value>>>,
+    /// base64 contents of binary fields, read without `bin_size`
+    contents: RefCell<BTreeMap<u32, Map<String, Value>>>,
 }
 
-impl RecordSet<'_> {
+impl<'a> RecordSet<'a> {
+    /// make sure fields `names` are loaded for every record of this set
+    ///
+    /// Missing values are fetched with a single `read` for all records, empty
+    /// `names` read all the fields again.
+    pub fn prefetch(&self, names: &[&str]) -> Result<()> {
+        let missing: Vec<u32> = {
+            let data = self.data.borrow();
+            self.ids
+                .iter()
+                .filter(|id| match data.get(id) {
+                    // no names means all fields
+                    Some(rec) if !names.is_empty() => {
+                        names.iter().any(|name| !rec.contains_key(*name))
+                    }
+                    _ => true,
+                })
+                .cloned()
+                .collect()
+        };
+        if missing.is_empty() {
+            return Ok(());
+        }
+        match self.model.read(&missing, names) {
+            Err(err) => Err(err),
+            Ok(rows) => {
+                let mut data = self.data.borrow_mut();
+                for row in rows {
+                    if let Value::Object(row) = row {
+                        if let Some(id) = row.get("id").and_then(|id| id.as_u64()) {
+                            data.entry(id as u32).or_default().extend(row);
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
     /// get attribute `name` for the first object of this record set
-    pub fn get(&self, name: &str) -> Option<&Value> {
-        let head = &self.data[0];
-        match head {
-            Value::Object(obj) => obj.get(name),
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.record(0)?.get(name)
+    }
+    /// get attribute `name` for the first object of this record set, failing if
+    /// it can not be loaded
+    pub fn try_get(&self, name: &str) -> Result<Option<Value>> {
+        match self.record(0) {
+            None => Ok(None),
+            Some(record) => record.try_get(name),
+        }
+    }
+    /// typed value of attribute `name` for the first object of this record set
+    pub fn value(&self, name: &str) -> Option<OdooValue> {
+        self.record(0)?.value(name)
+    }
+    /// set attribute `name` on the loaded records, without writing it to the server
+    pub fn set<T: Serialize>(&self, name: &str, value: T) {
+        let value = json!(value);
+        let mut data = self.data.borrow_mut();
+        for id in &self.ids {
+            data.entry(*id)
+                .or_default()
+                .insert(name.to_owned(), value.clone());
+        }
+    }
+    /// follow relational field `name`
+    ///
+    /// Returns the records of the comodel linked to any record of this set
+    /// (in order, without duplicates). The result is browsed lazily.
+    pub fn related(&self, name: &str) -> Result<RecordSet<'a>> {
+        let (type_, relation) = match self.model.desc.fields.get(name) {
+            Some(FieldDescriptor {
+                type_,
+                relation: Some(relation),
+                ..
+            }) => (type_.as_str(), relation),
             _ => {
-                unreachable!()
+                return Err(Error::from_kind(ErrorKind::InvalidField(format!(
+                    "{}.{} is not a relational field",
+                    self.model.desc.name, name
+                ))))
             }
+        };
+        self.prefetch(&[name])?;
+        let mut ids: Vec<u32> = Vec::new();
+        {
+            let data = self.data.borrow();
+            for id in &self.ids {
+                let linked = match data.get(id).and_then(|rec| rec.get(name)) {
+                    Some(Value::Array(values)) if type_ == "many2one" => {
+                        values.iter().take(1).collect::<Vec<_>>()
+                    }
+                    Some(Value::Array(values)) => values.iter().collect(),
+                    _ => vec![],
+                };
+                for id in linked.iter().filter_map(|id| id.as_u64()) {
+                    if !ids.contains(&(id as u32)) {
+                        ids.push(id as u32);
+                    }
+                }
+            }
+        }
+        match self.model.cli.get_model(relation) {
+            Err(err) => Err(err),
+            Ok(comodel) => Ok(comodel.browse_lazy(&ids)),
         }
     }
-    pub fn set<T>(&self, name: &str, value: T) {
-        let head = &self.data[0];
+    /// write `vals` on every record of this `RecordSet`
+    ///
+    /// x2many fields take a list of [`Command`]s, selection fields a known key.
+    /// `vals` are validated first, see [`ObjectDescriptor::validate`].
+    pub fn write(&self, vals: Value) -> Result<bool> {
+        match self.model.desc.validate(&vals, ValidationMode::Write) {
+            Err(err) => Err(err),
+            Ok(()) => self.write_unchecked(vals),
+        }
+    }
+    /// write `vals` on every record of this `RecordSet`, without validating them
+    pub fn write_unchecked(&self, vals: Value) -> Result<bool> {
+        match self.invalidated().model.execute("write", vec![json!(self.ids), vals]) {
+            Err(err) => Err(err),
+            Ok(res) => Ok(res.as_bool().unwrap_or(false)),
+        }
+    }
+    /// delete the records of this `RecordSet`
+    pub fn unlink(&self) -> Result<bool> {
+        match self.invalidated().model.execute("unlink", vec![json!(self.ids)]) {
+            Err(err) => Err(err),
+            Ok(res) => Ok(res.as_bool().unwrap_or(false)),
+        }
+    }
+    /// `(id, display_name)` for each record of this `RecordSet`
+    ///
+    /// Uses `name_get` on older servers and reads `display_name` on servers
+    /// where `name_get` has been removed.
+    pub fn display_names(&self) -> Result<Vec<(u32, String)>> {
+        if self.model.cli.server_major() >= DISPLAY_NAME_SERIE {
+            match self.model.read(&self.ids, &["display_name"]) {
+                Err(err) => Err(err),
+                Ok(data) => Ok(data
+                    .iter()
+                    .filter_map(|rec| {
+                        let id = rec.get("id").and_then(|id| id.as_u64())?;
+                        let name = rec.get("display_name").and_then(|n| n.as_str())?;
+                        Some((id as u32, name.to_owned()))
+                    })
+                    .collect()),
+            }
+        } else {
+            match self.model.execute("name_get", vec![json!(self.ids)]) {
+                Err(err) => Err(err),
+                Ok(names) => serde_json::from_value(names).chain_err(|| "invalid name_get result"),
+            }
+        }
+    }
+    /// drop the loaded and cached values of these records
+    pub fn invalidate_cache(&self) {
+        self.data.borrow_mut().clear();
+        self.contents.borrow_mut().clear();
+        self.model.invalidate_cache(None, Some(&self.ids));
+    }
+    fn invalidated(&self) -> &Self {
+        self.invalidate_cache();
+        self
+    }
+    /// call `method` on this `RecordSet`, its cached values are invalidated
+    ///
+    /// `args` are the positional arguments after the ids, see [`Args`],
+    /// `kwargs` an object of keyword arguments.
+    pub fn call<A: Into<Args>>(
+        &self,
+        method: &str,
+        args: A,
+        kwargs: Option<Value>,
+    ) -> Result<Value> {
+        match keywords(kwargs) {
+            Err(err) => Err(err),
+            Ok(kwargs) => self.call_kw(method, args.into().0, kwargs),
+        }
     }
-    /// call `method` on this `RecordSet`
-    pub fn call(&self, method: &str, args: Option<Value>, kwargs: Option<Value>) -> Result<Value> {
-        debug!("call {:?}::{}({:?})", self, method, args);
+    /// call `method` on this `RecordSet` with positional `args` and keyword `kwargs`
+    pub fn call_kw(
+        &self,
+        method: &str,
+        args: Vec<Value>,
+        kwargs: Map<String, Value>,
+    ) -> Result<Value> {
+        debug!("call {:?}::{}({:?}, {:?})", self, method, args, kwargs);
+        self.invalidate_cache();
+        let mut args = args;
+        args.insert(0, json!(self.ids));
         match &self.model.cli.session {
             None => Err(Error::from_kind(ErrorKind::NotConnected)),
-            Some(session) => self.model.cli.api.recordset_call(
+            Some(session) => self.model.cli.api.call_kw(
                 &session.db,
                 1,
                 "admin",
                 &self.model.desc.name,
-                Some(&self.ids),
                 method,
                 args,
                 kwargs,
@@ -471,54 +929,232 @@ impl<'a> Model<'a> {
             }
         }
     }
-    pub fn search(&self, domain: Value) -> Result<Vec<u32>> {
+    /// ids of the records matching `domain`
+    ///
+    /// See [`Model::with_domain_check`] to check `domain` before sending it.
+    pub fn search<D: IntoDomain>(&self, domain: D) -> Result<Vec<u32>> {
+        let domain = domain.into_domain()?;
+        self.check_domain(&domain)?;
         match &self.cli.session {
             None => Err(Error::from_kind(ErrorKind::NotConnected)),
-            Some(session) => {
-                self.cli
-                    .api
-                    .object_search(&session.db, 1, "admin", &self.desc.name, domain)
-            }
+            Some(session) => self.cli.api.object_search(
+                &session.db,
+                1,
+                "admin",
+                &self.desc.name,
+                domain.to_value(),
+            ),
+        }
+    }
+    /// number of records matching `domain`
+    pub fn search_count<D: IntoDomain>(&self, domain: D) -> Result<u64> {
+        let domain = domain.into_domain()?;
+        self.check_domain(&domain)?;
+        match self.execute("search_count", vec![domain.to_value()]) {
+            Err(err) => Err(err),
+            Ok(count) => serde_json::from_value(count).chain_err(|| "invalid search_count result"),
         }
     }
 
-    pub fn browse(&self, ids: &Vec<u32>) -> Result<RecordSet> {
-        let names = self
+    /// create a record from `vals`, returns its id
+    ///
+    /// x2many fields take a list of [`Command`]s, selection fields a known key.
+    /// `vals` are validated first, see [`ObjectDescriptor::validate`]: required
+    /// fields missing from `vals` must have a default value.
+    pub fn create(&self, vals: Value) -> Result<u32> {
+        let defaults = match &vals {
+            Value::Object(vals) => {
+                let missing = self.desc.missing_required(vals);
+                if missing.is_empty() {
+                    Map::new()
+                } else {
+                    self.default_get(&missing)?
+                }
+            }
+            _ => Map::new(),
+        };
+        match self
             .desc
+            .validate_with_defaults(&vals, ValidationMode::Create, &defaults)
+        {
+            Err(err) => Err(err),
+            Ok(()) => self.create_unchecked(vals),
+        }
+    }
+    /// create a record from `vals` without validating them, returns its id
+    pub fn create_unchecked(&self, vals: Value) -> Result<u32> {
+        match self.execute("create", vec![vals]) {
+            Err(err) => Err(err),
+            Ok(id) => serde_json::from_value(id).chain_err(|| "invalid create result"),
+        }
+    }
+
+    /// fuzzy search records by name, returns `(id, display_name)` pairs
+    ///
+    /// `operator` defaults to `ilike` on the server side when `None`.
+    pub fn name_search<D: IntoDomain>(
+        &self,
+        name: &str,
+        domain: D,
+        operator: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<Vec<(u32, String)>> {
+        let domain = domain.into_domain()?;
+        self.check_domain(&domain)?;
+        let args = vec![
+            json!(name),
+            domain.to_value(),
+            json!(operator.unwrap_or("ilike")),
+            json!(limit.unwrap_or(100)),
+        ];
+        match self.execute("name_search", args) {
+            Err(err) => Err(err),
+            Ok(found) => serde_json::from_value(found).chain_err(|| "invalid name_search result"),
+        }
+    }
+
+    /// create a record from its name only, returns `(id, display_name)`
+    pub fn name_create(&self, name: &str) -> Result<(u32, String)> {
+        match self.execute("name_create", vec![json!(name)]) {
+            Err(err) => Err(err),
+            Ok(created) => {
+                serde_json::from_value(created).chain_err(|| "invalid name_create result")
+            }
+        }
+    }
+
+    /// `RecordSet` of `ids`, fields are read when first accessed
+    pub fn browse_lazy(&self, ids: &[u32]) -> RecordSet<'a> {
+        RecordSet {
+            ids: ids.to_owned(),
+            model: self.clone(),
+            data: RefCell::new(BTreeMap::new()),
+            contents: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// `RecordSet` of `ids` with the default fields loaded
+    ///
+    /// Only cheap stored fields are read (see `FieldDescriptor::is_prefetched`),
+    /// others are loaded for the whole set the first time they are accessed.
+    pub fn browse(&self, ids: &[u32]) -> Result<RecordSet<'a>> {
+        self.browse_fields(ids, &self.default_fields())
+    }
+
+    /// `RecordSet` of `ids` with fields `names` loaded
+    pub fn browse_fields(&self, ids: &[u32], names: &[&str]) -> Result<RecordSet<'a>> {
+        let records = self.browse_lazy(ids);
+        match records.prefetch(names) {
+            Err(err) => Err(err),
+            Ok(()) => Ok(records),
+        }
+    }
+
+    /// fields read by `browse`
+    pub fn default_fields(&self) -> Vec<&str> {
+        self.desc
             .fields
             .iter()
+            .filter(|(_, desc)| desc.is_prefetched())
             .map(|(name, _)| name.as_str())
-            .collect::<Vec<&str>>();
-        match self.read(&ids, &names) {
-            Err(err) => Err(err),
-            Ok(data) => Ok(RecordSet {
-                ids: ids.to_owned(),
-                model: self,
-                data,
-            }),
+            .collect()
+    }
+
+    /// declare fields loaded together: accessing any of them loads them all
+    pub fn with_prefetch_group(mut self, names: &[&str]) -> Self {
+        self.prefetch_groups
+            .push(names.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
+    /// check search domains against the model fields before sending them
+    ///
+    /// `search`, `search_count`, `name_search`, `read_group` and
+    /// `RecordSet::filtered_domain` then run
+    /// [`ObjectDescriptor::validate_domain`], dotted paths cost a
+    /// `fields_get` on each comodel not loaded yet.
+    pub fn with_domain_check(mut self) -> Self {
+        self.check_domains = true;
+        self
+    }
+    /// check `domain` if enabled with [`Model::with_domain_check`]
+    pub(crate) fn check_domain(&self, domain: &Domain) -> Result<()> {
+        if self.check_domains {
+            self.desc.validate_domain(domain, self.cli)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// fields to load along with field `name`
+    pub(crate) fn prefetch_fields<'n>(&'n self, name: &'n str) -> Vec<&'n str> {
+        match self
+            .prefetch_groups
+            .iter()
+            .find(|group| group.iter().any(|field| field == name))
+        {
+            Some(group) => group.iter().map(|field| field.as_str()).collect(),
+            None => vec![name],
         }
     }
 
-    pub fn search_browse(&self, domain: Value) -> Result<RecordSet> {
+    pub fn search_browse<D: IntoDomain>(&self, domain: D) -> Result<RecordSet<'a>> {
         match self.search(domain) {
             Err(err) => Err(err),
             Ok(ids) => self.browse(&ids),
         }
     }
 
-    pub fn read(&self, ids: &Vec<u32>, names: &Vec<&str>) -> Result<Vec<Value>> {
+    /// call `method` on the model with positional `args`, see [`Args`]
+    ///
+    /// Cached records of the model are invalidated, unless `method` is known
+    /// not to change them (`read`, `search`, `fields_get`...).
+    pub fn execute<A: Into<Args>>(&self, method: &str, args: A) -> Result<Value> {
+        if cache::invalidates(method) {
+            self.invalidate_cache(None, None);
+        }
+        match &self.cli.session {
+            None => Err(Error::from_kind(ErrorKind::NotConnected)),
+            Some(session) => {
+                self.cli
+                    .api
+                    .object_execute(&session.db, 1, "admin", &self.desc.name, method, args)
+            }
+        }
+    }
+
+    /// read fields `names` of records `ids`, going through the client cache
+    pub fn read(&self, ids: &[u32], names: &[&str]) -> Result<Vec<Value>> {
+        let (mut rows, missing) = self.cli.cache.borrow_mut().lookup(&self.desc.name, ids, names);
+        if missing.is_empty() {
+            return Ok(rows);
+        }
         match &self.cli.session {
             None => Err(Error::from_kind(ErrorKind::NotConnected)),
             Some(session) => {
-                let data =
-                    self.cli
-                        .api
-                        .object_read(&session.db, 1, "admin", &self.desc.name, ids, names);
+                let data = self.cli.api.object_read(
+                    &session.db,
+                    1,
+                    "admin",
+                    &self.desc.name,
+                    &missing,
+                    names,
+                );
                 match data {
                     Err(err) => Err(err),
                     Ok(data) => match serde_json::from_value::<Vec<Value>>(data) {
                         Err(err) => Err(Error::from_kind(ErrorKind::JsonError(err))),
-                        Ok(data) => Ok(data),
+                        Ok(data) => {
+                            self.cli.cache.borrow_mut().store(&self.desc.name, &data);
+                            rows.extend(data);
+                            let position = |row: &Value| {
+                                row.get("id")
+                                    .and_then(|id| id.as_u64())
+                                    .and_then(|id| ids.iter().position(|i| u64::from(*i) == id))
+                            };
+                            rows.sort_by_key(position);
+                            Ok(rows)
+                        }
                     },
                 }
             }
@@ -640,33 +1276,12 @@ impl OdooApi {
             Ok(values) => {
                 let mut fields = BTreeMap::<String, FieldDescriptor>::new();
                 for (attr, obj) in values.iter() {
-                    let desc = serde_json::from_value(obj.to_owned());
-                    match desc {
-                        Ok(desc) => {
+                    match FieldDescriptor::from_value(obj) {
+                        Some(desc) => {
                             fields.insert(attr.to_owned(), desc);
                         }
-                        Err(err) => {
-                            if let Some(ro) = obj.get("readonly") {
-                                let ro: bool = match ro {
-                                    Value::Number(n) => {
-                                        if n.as_i64() == Some(0) {
-                                            false
-                                        } else {
-                                            true
-                                        }
-                                    }
-                                    _ => false,
-                                };
-                                let mut changed = obj.clone();
-                                debug!("RO: {:?}", ro);
-                                changed["readonly"] = json!(ro);
-                                let desc = serde_json::from_value(changed).unwrap();
-                                // debug!("{} = {:#?}\n", attr, desc);
-                                fields.insert(attr.to_owned(), desc);
-                            } else {
-                                debug!("Could not get field descriptor for {}: {}", attr, err);
-                                //debug!("{}\n\n", serde_json::to_string_pretty(value).unwrap());
-                            }
+                        None => {
+                            log::warn!("field {}.{} is not described: {}", object, attr, obj);
                         }
                     }
                 }
@@ -687,47 +1302,75 @@ impl OdooApi {
         object: &str,
         domain: Value,
     ) -> Result<Vec<u32>> {
-        let args = json!(
-            [db, uid, login, object, "search", (domain,),
-             {
-                "context": {
-                    "lang": "en_US",
-                    "current_week": "2108",
-                    "tz": "Europe/Paris",
-                    "uid": 1,
-                    "current_week2": "2109"
-                }
-            }]
-        );
-        let resp = self.odoo_service_call(&OBJECT_SERVICE, "execute_kw", args);
-        self.rpc.decode_response::<Vec<u32>>(resp)
+        match self.call_kw(db, uid, login, object, "search", vec![domain], Map::new()) {
+            Err(err) => Err(err),
+            Ok(ids) => serde_json::from_value(ids).chain_err(|| "invalid search result"),
+        }
     }
+    /// read `fields` of `ids`, binary fields are read as their size (`bin_size`)
     pub fn object_read(
         &self,
         db: &str,
         uid: u32,
         login: &str,
         object: &str,
-        ids: &Vec<u32>,
-        fields: &Vec<&str>,
+        ids: &[u32],
+        fields: &[&str],
     ) -> Result<Value> {
-        let ids = json!(ids);
-        let args = json!(
-            [db, uid, login, object, "read", (ids, fields),
-             {
-                "context": {
-                    "lang": "en_US",
-                    "current_week": "2108",
-                    "tz": "Europe/Paris",
-                    "uid": 1,
-                    "current_week2": "2109"
-                }
-            }]
-        );
+        let mut kwargs = Map::new();
+        kwargs.insert("context".to_owned(), json!({"bin_size": true}));
+        self.call_kw(
+            db,
+            uid,
+            login,
+            object,
+            "read",
+            vec![json!(ids), json!(fields)],
+            kwargs,
+        )
+    }
+    /// call `method` on `object` with positional `args`, see [`Args`]
+    pub fn object_execute<A: Into<Args>>(
+        &self,
+        db: &str,
+        uid: u32,
+        login: &str,
+        object: &str,
+        method: &str,
+        args: A,
+    ) -> Result<Value> {
+        self.call_kw(db, uid, login, object, method, args.into().0, Map::new())
+    }
+    /// call `method` on `object` with positional `args` and keyword `kwargs`
+    ///
+    /// A `context` given in `kwargs` is merged over the default context.
+    #[allow(clippy::too_many_arguments)]
+    pub fn call_kw(
+        &self,
+        db: &str,
+        uid: u32,
+        login: &str,
+        object: &str,
+        method: &str,
+        args: Vec<Value>,
+        kwargs: Map<String, Value>,
+    ) -> Result<Value> {
+        let mut kwargs = kwargs;
+        let mut context = default_context();
+        if let Some(Value::Object(extra)) = kwargs.remove("context") {
+            context.extend(extra);
+        }
+        kwargs.insert("context".to_owned(), Value::Object(context));
+        let args = json!([db, uid, login, object, method, args, kwargs]);
         let resp = self.odoo_service_call(&OBJECT_SERVICE, "execute_kw", args);
         self.rpc.decode_response::<Value>(resp)
     }
-    pub fn recordset_call(
+    /// call `method` on records `ids` of `object` (on the model if `None`)
+    ///
+    /// `args` are the positional arguments, see [`Args`], `kwargs` an object
+    /// of keyword arguments.
+    #[allow(clippy::too_many_arguments)]
+    pub fn recordset_call<A: Into<Args>>(
         &self,
         db: &str,
         uid: u32,
@@ -735,64 +1378,72 @@ impl OdooApi {
         object: &str,
         ids: Option<&Vec<u32>>,
         method: &str,
-        args: Option<Value>,
-        _kwargs: Option<Value>,
+        args: A,
+        kwargs: Option<Value>,
     ) -> Result<Value> {
-        let args = match (ids, args) {
-            (Some(ids), Some(args)) => {
-                json!([
-                    db, uid, login, object, method, (ids, args),
-                    {
-                        "context": {
-                            "lang": "en_US",
-                            "current_week": "2108",
-                            "tz": "Europe/Paris",
-                            "uid": 1,
-                            "current_week2": "2109"
-                        }
-                    }
-                ])
-            }
-            (Some(ids), None) => json!([
-            db, uid, login, object, method, (ids,),
-            {
-                "context": {
-                    "lang": "en_US",
-                    "current_week": "2108",
-                    "tz": "Europe/Paris",
-                    "uid": 1,
-                    "current_week2": "2109"
-                }
-            }
-            ]),
-            (None, Some(args)) => json!([
-            db, uid, login, object, method, (args,),
-            {
-                "context": {
-                    "lang": "en_US",
-                    "current_week": "2108",
-                    "tz": "Europe/Paris",
-                    "uid": 1,
-                    "current_week2": "2109"
-                }
-            }
-            ]),
-            (None, None) => json!([
-            db, uid, login, object, method, [],
-            {
-                "context": {
-                    "lang": "en_US",
-                    "current_week": "2108",
-                    "tz": "Europe/Paris",
-                    "uid": 1,
-                    "current_week2": "2109"
-                }
-            }
+        let kwargs = keywords(kwargs)?;
+        let mut positional = args.into().0;
+        if let Some(ids) = ids {
+            positional.insert(0, json!(ids));
+        }
+        self.call_kw(db, uid, login, object, method, positional, kwargs)
+    }
+}
 
-            ]),
-        };
-        let resp = self.odoo_service_call(&OBJECT_SERVICE, "execute_kw", args);
-        self.rpc.decode_response::<Value>(resp)
+/// context sent along every method call
+fn default_context() -> Map<String, Value> {
+    match json!({
+        "lang": "en_US",
+        "current_week": "2108",
+        "tz": "Europe/Paris",
+        "uid": 1,
+        "current_week2": "2109"
+    }) {
+        Value::Object(context) => context,
+        _ => unreachable!(),
+    }
+}
+
+/// positional arguments of a method call
+///
+/// Only a `Vec` is spread into several arguments, any JSON value (a list
+/// too) is passed as the only argument and `None` passes none:
+///
+/// ```ignore
+/// labels.call("action_assign", None, None)?;
+/// labels.call("set_tags", json!([3, 4]), None)?; // one argument, a list
+/// labels.call("move_to", vec![json!(8), json!(true)], None)?; // two arguments
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args(pub Vec<Value>);
+
+impl From<Vec<Value>> for Args {
+    fn from(args: Vec<Value>) -> Args {
+        Args(args)
+    }
+}
+
+impl From<Value> for Args {
+    fn from(arg: Value) -> Args {
+        Args(vec![arg])
+    }
+}
+
+impl From<Option<Value>> for Args {
+    fn from(arg: Option<Value>) -> Args {
+        Args(arg.into_iter().collect())
+    }
+}
+
+/// keyword arguments, must be an object
+fn keywords(kwargs: Option<Value>) -> Result<Map<String, Value>> {
+    match kwargs {
+        None | Some(Value::Null) => Ok(Map::new()),
+        Some(Value::Object(kwargs)) => Ok(kwargs),
+        Some(other) => Err(Error::from_kind(ErrorKind::MyOtherError(format!(
+            "keyword arguments must be an object, got {}",
+            other
+        )))),
     }
 }
 
@@ -824,7 +1475,7 @@ impl<'a> DBService<'a> {
                     match line {
                         Ok(val) => {
                             let data = base64::decode(val).unwrap();
-                            writer.write(&data).unwrap();
+                            writer.write_all(&data).unwrap();
                         }
                         Err(err) => {
                             debug!("err: {:#?}", err);
@@ -842,7 +1493,7 @@ impl<'a> DBService<'a> {
             "duplicate_database",
             json!([master_password, db, new_db])
         ) {
-            Err(err) =>  { return Err(err) }
+            Err(err) => Err(err),
             Ok(resp) => {
                 let res: Result<Value> = serde_json::from_reader(resp).chain_err(|| "huu") ;
 
@@ -859,9 +1510,7 @@ impl<'a> DBService<'a> {
         let res: Result<String> = fs::read_to_string(path).chain_err(|| "foobar");
         match res {
             Err(err) => Err(err),
-            Ok(content) => {
-                let encoded = base64::encode(content);
-                
+            Ok(_content) => {
                 let resp = self.cli.api.odoo_service_call(
                     &DB_SERVICE, "dump", json!([master_password, db, "zip"]));
                 let data = self.cli.api.rpc.decode_response::<Value>(resp); // FIXME: allocating a whole data dump is bad ...
@@ -890,14 +1539,12 @@ impl<'a> DBService<'a> {
             "create_database",
             json!([master_password, db, demo, lang, admin_password]),
         );
-        let result = self.cli.api.rpc.decode_response::<Value>(resp);
-        result
+        self.cli.api.rpc.decode_response::<Value>(resp)
     }
 
     pub fn drop(&self, master_password: &str, db: &str) -> Result<Value> {
         let resp = self.cli.api.odoo_service_call(&DB_SERVICE, "drop", json!([master_password, db]));
-        let result = self.cli.api.rpc.decode_response::<Value>(resp);
-        result
+        self.cli.api.rpc.decode_response::<Value>(resp)
     }
 }
 
@@ -938,14 +1585,159 @@ extern crate lazy_static;
 
 #[cfg(test)]
 mod tests {
-    use crate::odoo_url_from_env;
+    use crate::{
+        keywords, odoo_url_from_env, Args, Domain, FieldDescriptor, Model, ObjectDescriptor,
+        OdooClient, RecordSet,
+    };
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
     use std::env;
+    use std::rc::Rc;
     use std::sync::{Arc, Mutex};
     use url::Url;
     lazy_static! {
         static ref LOCK: Arc<Mutex<u32>> = Arc::new(Mutex::new(0));
     }
 
+    /// client that is never connected, for tests that do not hit a server
+    pub(crate) fn offline_client() -> OdooClient {
+        let lock = Arc::clone(&LOCK);
+        let _data = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        env::set_var("ODOO_URL", "http://localhost:8069");
+        OdooClient::new()
+    }
+
+    /// model `name` with `fields` given as `(name, type)`
+    pub(crate) fn offline_model<'a>(
+        cli: &'a OdooClient,
+        name: &str,
+        fields: &[(&str, &str)],
+    ) -> Model<'a> {
+        let fields = fields
+            .iter()
+            .map(|(name, type_)| {
+                (
+                    name.to_string(),
+                    FieldDescriptor {
+                        type_: type_.to_string(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        Model::new(
+            Rc::new(ObjectDescriptor {
+                name: name.to_owned(),
+                fields,
+            }),
+            cli,
+        )
+    }
+
+    /// records of `model` already loaded with `rows` (each with an `id`)
+    pub(crate) fn offline_records<'a>(model: &Model<'a>, rows: Value) -> RecordSet<'a> {
+        let rows = rows.as_array().cloned().unwrap_or_default();
+        let ids: Vec<u32> = rows
+            .iter()
+            .filter_map(|row| row.get("id").and_then(|id| id.as_u64()))
+            .map(|id| id as u32)
+            .collect();
+        let records = model.browse_lazy(&ids);
+        {
+            let mut data = records.data.borrow_mut();
+            for (id, row) in ids.iter().zip(rows) {
+                if let Value::Object(row) = row {
+                    data.insert(*id, row);
+                }
+            }
+        }
+        records
+    }
+
+    #[test]
+    fn test_call_arguments() {
+        assert_eq!(Args::from(None).0, Vec::<Value>::new());
+        assert_eq!(Args::from(Some(json!("data"))).0, vec![json!("data")]);
+        assert_eq!(Args::from(json!([1, 2])).0, vec![json!([1, 2])]);
+        assert_eq!(
+            Args::from(vec![json!(1), json!([2, 3])]).0,
+            vec![json!(1), json!([2, 3])]
+        );
+
+        assert!(keywords(None).unwrap().is_empty());
+        assert_eq!(
+            keywords(Some(json!({"say": "hello"}))).unwrap()["say"],
+            json!("hello")
+        );
+        assert!(keywords(Some(json!([1]))).is_err());
+    }
+
+    #[test]
+    fn test_prefetched_fields() {
+        let field = |type_: &str, store: bool| FieldDescriptor {
+            type_: type_.to_owned(),
+            store,
+            ..Default::default()
+        };
+        assert!(field("char", true).is_prefetched());
+        assert!(field("many2one", true).is_prefetched());
+        assert!(!field("char", false).is_prefetched());
+        assert!(!field("binary", true).is_prefetched());
+        assert!(!field("many2many", true).is_prefetched());
+    }
+
+    #[test]
+    fn test_domain_check_opt_in() {
+        let cli = offline_client();
+        let model = offline_model(&cli, "stock.label", &[("name", "char")]);
+        let domain = Domain::from_value(&json!([["nmae", "=", "L-0001"]])).unwrap();
+        assert!(model.check_domain(&domain).is_ok());
+        assert!(model.with_domain_check().check_domain(&domain).is_err());
+    }
+
+    #[test]
+    fn test_field_descriptor_parsing() {
+        let state = FieldDescriptor::from_value(&json!({
+            "type": "selection",
+            "string": "Status",
+            "readonly": 1,
+            "store": true,
+            "selection": [["draft", "Draft"], [1, "Done"]],
+            "states": {"draft": [["readonly", false]]},
+            "exportable": true
+        }))
+        .unwrap();
+        assert!(state.readonly);
+        assert_eq!(
+            state.selection,
+            vec![
+                ("draft".to_owned(), "Draft".to_owned()),
+                ("1".to_owned(), "Done".to_owned())
+            ]
+        );
+        assert_eq!(state.extra.get("exportable"), Some(&json!(true)));
+        assert_eq!(state.states, json!({"draft": [["readonly", false]]}));
+
+        let amount = FieldDescriptor::from_value(&json!({
+            "type": "monetary",
+            "digits": [16, 2],
+            "currency_field": "currency_id",
+            "size": false,
+            "relation": false,
+            "groups": "base.group_user"
+        }))
+        .unwrap();
+        assert_eq!(amount.digits, Some((16, 2)));
+        assert_eq!(amount.currency_field.as_deref(), Some("currency_id"));
+        assert_eq!(amount.size, None);
+        assert_eq!(amount.relation, None);
+        assert_eq!(amount.groups.as_deref(), Some("base.group_user"));
+
+        let odd = FieldDescriptor::from_value(&json!({"type": "char", "help": "x"})).unwrap();
+        assert_eq!(odd.type_, "char");
+        assert!(FieldDescriptor::from_value(&json!(false)).is_none());
+    }
+
     #[test]
     fn test_odoo_default_url() {
         let lock = Arc::clone(&LOCK);
@@ -1,3 +1,4 @@
+#![allow(clippy::result_large_err)]
 // extern crate base64;
 // extern crate dotenv;
 // extern crate jsonrpc_client_core;
@@ -43,7 +44,7 @@ fn main() -> Result<(), Error> {
         Ok(ids) => {
             match stock_label.read(
                 &ids,
-                &vec![
+                &[
                     "name",
                     "product_id",
                     "product_tag_ids",
@@ -115,7 +116,7 @@ fn main() -> Result<(), Error> {
 
     let res = term.call(
         "foobar",
-        Some(json!((1, 2, 3))),
+        vec![json!(1), json!(2), json!(3)],
         Some(json!({"say": "Viva l'Algérie !"})),
     );
     println!("foobar result: {:#?}", res);
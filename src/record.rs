@@ -0,0 +1,482 @@
+//! per-record views and recordset operations
+//!
+//! ```ignore
+//! for label in &labels {
+//!     println!("{}: {:?}", label.id(), label.get("name"));
+//! }
+//! let terminals = labels.filtered(|label| label.get("is_terminal") == Some(json!(true)));
+//! let drafts = labels.filtered_domain(domain!(state == "draft"))?;
+//! let first = labels.record(0); // no `labels[0]`, see `RecordSet::record`
+//! ```
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
+
+use crate::{Domain, Error, ErrorKind, IntoDomain, OdooValue, Operator, RecordSet, Result};
+
+/// view on a single record of a `RecordSet`
+#[derive(Clone, Copy)]
+pub struct Record<'r, 'a> {
+    set: &'r RecordSet<'a>,
+    id: u32,
+}
+
+impl<'r, 'a> Record<'r, 'a> {
+    /// database id of the record
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    /// get attribute `name` of this record
+    ///
+    /// The field (and its prefetch group) is loaded for the whole parent
+    /// recordset at once. A loading error is logged and gives `None`, see
+    /// [`Record::try_get`] to handle it.
+    pub fn get(&self, name: &str) -> Option<Value> {
+        match self.try_get(name) {
+            Ok(value) => value,
+            Err(err) => {
+                log::error!("could not load {}.{}: {}", self.set.model.name(), name, err);
+                None
+            }
+        }
+    }
+    /// get attribute `name` of this record, failing if it can not be loaded
+    pub fn try_get(&self, name: &str) -> Result<Option<Value>> {
+        self.set.prefetch(&self.set.model.prefetch_fields(name))?;
+        Ok(self
+            .set
+            .data
+            .borrow()
+            .get(&self.id)
+            .and_then(|rec| rec.get(name))
+            .cloned())
+    }
+    /// typed value of attribute `name`, decoded with its field descriptor
+    pub fn value(&self, name: &str) -> Option<OdooValue> {
+        let desc = self.set.model.desc.fields.get(name)?;
+        self.get(name).map(|value| OdooValue::decode(desc, &value))
+    }
+    /// the recordset this record belongs to
+    pub(crate) fn recordset(&self) -> &'r RecordSet<'a> {
+        self.set
+    }
+    /// this record as a single-record `RecordSet`
+    pub fn to_recordset(&self) -> RecordSet<'a> {
+        self.set.subset(vec![self.id])
+    }
+}
+
+impl std::fmt::Debug for Record<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.debug_struct("Record")
+            .field("name", &self.set.model.name())
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+/// iterator over the records of a `RecordSet`
+pub struct Records<'r, 'a> {
+    set: &'r RecordSet<'a>,
+    ids: std::slice::Iter<'r, u32>,
+}
+
+impl<'r, 'a> Iterator for Records<'r, 'a> {
+    type Item = Record<'r, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let set = self.set;
+        self.ids.next().map(|id| Record { set, id: *id })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ids.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Records<'_, '_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let set = self.set;
+        self.ids.next_back().map(|id| Record { set, id: *id })
+    }
+}
+
+impl ExactSizeIterator for Records<'_, '_> {}
+
+impl<'r, 'a> IntoIterator for &'r RecordSet<'a> {
+    type Item = Record<'r, 'a>;
+    type IntoIter = Records<'r, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> RecordSet<'a> {
+    /// number of records
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+    /// is this recordset empty?
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+    /// iterate over the records
+    pub fn iter(&self) -> Records<'_, 'a> {
+        Records {
+            set: self,
+            ids: self.ids.iter(),
+        }
+    }
+    /// record at position `index`, `None` if out of bounds
+    ///
+    /// This stands for `labels[index]`: `Index` must return a reference and
+    /// a `Record` is a view built on demand, there is nothing to borrow it
+    /// from. Use [`RecordSet::slice`] for ranges.
+    pub fn record(&self, index: usize) -> Option<Record<'_, 'a>> {
+        self.ids.get(index).map(|id| Record { set: self, id: *id })
+    }
+    /// records at positions `range`, out of bounds positions are ignored
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> RecordSet<'a> {
+        let start = match range.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => end + 1,
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => self.ids.len(),
+        };
+        let end = end.min(self.ids.len());
+        let start = start.min(end);
+        self.subset(self.ids[start..end].to_vec())
+    }
+    /// records for which `predicate` holds
+    pub fn filtered<F>(&self, predicate: F) -> RecordSet<'a>
+    where
+        F: Fn(&Record) -> bool,
+    {
+        let ids = self
+            .iter()
+            .filter(|rec| predicate(rec))
+            .map(|rec| rec.id)
+            .collect();
+        self.subset(ids)
+    }
+    /// records matching `domain`, evaluated locally with [`Domain::matches`]
+    ///
+    /// The leaf fields are prefetched in one `read`. Dotted paths and
+    /// `any` / `not any` follow the relation with [`RecordSet::related`] and
+    /// filter the comodel records the same way.
+    pub fn filtered_domain<D: IntoDomain>(&self, domain: D) -> Result<RecordSet<'a>> {
+        let domain = domain.into_domain()?;
+        self.model.check_domain(&domain)?;
+        let domain = self.inline_relations(domain)?;
+        let mut names: Vec<&str> = domain
+            .leaves()
+            .into_iter()
+            .map(|(name, _, _)| name)
+            .filter(|name| *name != "id")
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        if !names.is_empty() {
+            self.prefetch(&names)?;
+        }
+        let mut ids = Vec::new();
+        {
+            let data = self.data.borrow();
+            for id in &self.ids {
+                let mut record = data.get(id).cloned().unwrap_or_default();
+                record.insert("id".to_owned(), json!(id));
+                if domain.matches(&record)? {
+                    ids.push(*id);
+                }
+            }
+        }
+        Ok(self.subset(ids))
+    }
+    /// `domain` with relations followed: leaves only name fields of this model
+    fn inline_relations(&self, domain: Domain) -> Result<Domain> {
+        let inline_all = |domains: Vec<Domain>| -> Result<Vec<Domain>> {
+            domains
+                .into_iter()
+                .map(|domain| self.inline_relations(domain))
+                .collect()
+        };
+        Ok(match domain {
+            Domain::Leaf { field, op, value } => match field.split_once('.') {
+                Some((name, path)) => {
+                    let linked = self.related(name)?.filtered_domain(Domain::Leaf {
+                        field: path.to_owned(),
+                        op,
+                        value,
+                    })?;
+                    Domain::leaf(name, Operator::In, &linked.ids)
+                }
+                None if matches!(op, Operator::Any | Operator::NotAny) => {
+                    let linked = self
+                        .related(&field)?
+                        .filtered_domain(Domain::from_value(&value)?)?;
+                    let op = if op == Operator::Any {
+                        Operator::In
+                    } else {
+                        Operator::NotIn
+                    };
+                    Domain::leaf(&field, op, &linked.ids)
+                }
+                None => Domain::Leaf { field, op, value },
+            },
+            Domain::And(domains) => Domain::And(inline_all(domains)?),
+            Domain::Or(domains) => Domain::Or(inline_all(domains)?),
+            Domain::Not(domain) => Domain::Not(Box::new(self.inline_relations(*domain)?)),
+            domain => domain,
+        })
+    }
+    /// values of field `name`, one per record (`Null` when missing)
+    pub fn mapped(&self, name: &str) -> Result<Vec<Value>> {
+        self.prefetch(&[name])?;
+        let data = self.data.borrow();
+        Ok(self
+            .ids
+            .iter()
+            .map(|id| {
+                data.get(id)
+                    .and_then(|rec| rec.get(name))
+                    .cloned()
+                    .unwrap_or(Value::Null)
+            })
+            .collect())
+    }
+    /// records sorted by `key` (stable)
+    pub fn sorted<K, F>(&self, key: F) -> RecordSet<'a>
+    where
+        K: Ord,
+        F: Fn(&Record) -> K,
+    {
+        let mut records: Vec<(K, u32)> = self.iter().map(|rec| (key(&rec), rec.id)).collect();
+        records.sort_by(|left, right| left.0.cmp(&right.0));
+        self.subset(records.into_iter().map(|(_, id)| id).collect())
+    }
+    /// records of `self` followed by records of `other` not in `self`
+    pub fn union(&self, other: &RecordSet<'a>) -> Result<RecordSet<'a>> {
+        self.check_same_model(other)?;
+        let mut ids = self.ids.clone();
+        for id in &other.ids {
+            if !ids.contains(id) {
+                ids.push(*id);
+            }
+        }
+        let records = self.subset(ids);
+        records.merge_data(other);
+        Ok(records)
+    }
+    /// records of `self` also in `other`
+    pub fn intersection(&self, other: &RecordSet<'a>) -> Result<RecordSet<'a>> {
+        self.check_same_model(other)?;
+        let ids = self
+            .ids
+            .iter()
+            .filter(|id| other.ids.contains(id))
+            .cloned()
+            .collect();
+        Ok(self.subset(ids))
+    }
+    /// records of `self` not in `other`
+    pub fn difference(&self, other: &RecordSet<'a>) -> Result<RecordSet<'a>> {
+        self.check_same_model(other)?;
+        let ids = self
+            .ids
+            .iter()
+            .filter(|id| !other.ids.contains(id))
+            .cloned()
+            .collect();
+        Ok(self.subset(ids))
+    }
+
+    /// recordset of the same model for `ids`, keeping values already loaded
+    pub(crate) fn subset(&self, ids: Vec<u32>) -> RecordSet<'a> {
+        let data = {
+            let loaded = self.data.borrow();
+            ids.iter()
+                .filter_map(|id| loaded.get(id).map(|rec| (*id, rec.clone())))
+                .collect::<BTreeMap<_, _>>()
+        };
+        RecordSet {
+            ids: ids.into_iter().fold(Vec::new(), |mut ids, id| {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+                ids
+            }),
+            model: self.model.clone(),
+            data: RefCell::new(data),
+            contents: RefCell::new(BTreeMap::new()),
+        }
+    }
+    fn merge_data(&self, other: &RecordSet<'a>) {
+        let theirs = other.data.borrow();
+        let mut ours = self.data.borrow_mut();
+        for id in &self.ids {
+            if let Some(rec) = theirs.get(id) {
+                let entry = ours.entry(*id).or_default();
+                for (name, value) in rec {
+                    entry
+                        .entry(name.to_owned())
+                        .or_insert_with(|| value.clone());
+                }
+            }
+        }
+    }
+    fn check_same_model(&self, other: &RecordSet<'a>) -> Result<()> {
+        if self.model.name() == other.model.name() {
+            Ok(())
+        } else {
+            Err(Error::from_kind(ErrorKind::ModelMismatch(
+                self.model.name().to_owned(),
+                other.model.name().to_owned(),
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{offline_client, offline_model, offline_records};
+    use crate::OdooValue;
+    use serde_json::json;
+
+    #[test]
+    fn test_iterate_records() {
+        let cli = offline_client();
+        let model = offline_model(&cli, "stock.label", &[("name", "char"), ("qty", "integer")]);
+        let labels = offline_records(
+            &model,
+            json!([{"id": 1, "name": "A"}, {"id": 2, "name": "B"}, {"id": 3, "name": "C"}]),
+        );
+        assert_eq!(labels.len(), 3);
+        let names: Vec<_> = labels.iter().map(|rec| rec.get("name").unwrap()).collect();
+        assert_eq!(names, vec![json!("A"), json!("B"), json!("C")]);
+        assert_eq!(labels.record(1).unwrap().id(), 2);
+        assert_eq!(
+            labels.record(2).unwrap().value("name"),
+            Some(OdooValue::Char("C".to_owned()))
+        );
+        assert!(labels.record(3).is_none());
+        assert_eq!(labels.slice(1..).ids, vec![2, 3]);
+        assert_eq!(labels.slice(..10).ids, vec![1, 2, 3]);
+        assert_eq!(
+            labels.slice(1..=1).record(0).unwrap().get("name"),
+            Some(json!("B"))
+        );
+        // `qty` is not loaded and the offline client can not read it
+        let first = labels.record(0).unwrap();
+        assert!(first.try_get("qty").is_err());
+        assert_eq!(first.get("qty"), None);
+    }
+
+    #[test]
+    fn test_empty_recordset() {
+        let cli = offline_client();
+        let model = offline_model(&cli, "stock.label", &[("name", "char")]);
+        let empty = model.browse_lazy(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.get("name"), None);
+        assert_eq!(empty.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_functional_helpers() {
+        let cli = offline_client();
+        let model = offline_model(&cli, "stock.label", &[("name", "char"), ("qty", "integer")]);
+        let labels = offline_records(
+            &model,
+            json!([
+                {"id": 1, "name": "C", "qty": 3},
+                {"id": 2, "name": "A", "qty": 0},
+                {"id": 3, "name": "B", "qty": 5}
+            ]),
+        );
+        let stocked = labels.filtered(|rec| rec.get("qty").and_then(|q| q.as_u64()) > Some(0));
+        assert_eq!(stocked.ids, vec![1, 3]);
+        assert_eq!(
+            stocked.mapped("name").unwrap(),
+            vec![json!("C"), json!("B")]
+        );
+        let sorted = labels.sorted(|rec| rec.get("name").unwrap().as_str().unwrap().to_owned());
+        assert_eq!(sorted.ids, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_filtered_domain() {
+        let cli = offline_client();
+        let model = offline_model(
+            &cli,
+            "stock.label",
+            &[
+                ("name", "char"),
+                ("qty", "integer"),
+                ("product_id", "many2one"),
+            ],
+        );
+        let labels = offline_records(
+            &model,
+            json!([
+                {"id": 1, "name": "L-0001", "qty": 3, "product_id": [7, "Apple"]},
+                {"id": 2, "name": "L-0002", "qty": 0, "product_id": false},
+                {"id": 3, "name": "X-0003", "qty": 5, "product_id": [8, "Pear"]}
+            ]),
+        );
+        let found = labels
+            .filtered_domain(json!([("qty", ">", 0), ("name", "=like", "L-%")]))
+            .unwrap();
+        assert_eq!(found.ids, vec![1]);
+        let found = labels
+            .filtered_domain(json!([
+                "|",
+                ("product_id", "=", "Pear"),
+                ("product_id", "=", false)
+            ]))
+            .unwrap();
+        assert_eq!(found.ids, vec![2, 3]);
+        assert!(labels
+            .filtered_domain(json!([("nmae", "=", "L-0001")]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_prefetch_groups() {
+        let cli = offline_client();
+        let model = offline_model(
+            &cli,
+            "stock.label",
+            &[
+                ("os_version", "char"),
+                ("app_version", "char"),
+                ("name", "char"),
+            ],
+        )
+        .with_prefetch_group(&["os_version", "app_version"]);
+        assert_eq!(
+            model.prefetch_fields("app_version"),
+            vec!["os_version", "app_version"]
+        );
+        assert_eq!(model.prefetch_fields("name"), vec!["name"]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let cli = offline_client();
+        let model = offline_model(&cli, "stock.label", &[("name", "char")]);
+        let left = offline_records(&model, json!([{"id": 3}, {"id": 1}, {"id": 2}]));
+        let right = offline_records(&model, json!([{"id": 4}, {"id": 2}, {"id": 3}]));
+        assert_eq!(left.union(&right).unwrap().ids, vec![3, 1, 2, 4]);
+        assert_eq!(left.intersection(&right).unwrap().ids, vec![3, 2]);
+        assert_eq!(left.difference(&right).unwrap().ids, vec![1]);
+
+        let other = offline_model(&cli, "res.users", &[]);
+        let users = offline_records(&other, json!([{"id": 1}]));
+        assert!(left.union(&users).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+//! selection fields
+//!
+//! Values written to selection fields, and compared to them in domains, are
+//! checked against the keys listed by `fields_get`, see
+//! [`ObjectDescriptor::validate`](crate::ObjectDescriptor::validate) and
+//! [`ObjectDescriptor::validate_domain`](crate::ObjectDescriptor::validate_domain).
+//!
+//! ```ignore
+//! #[derive(Debug, Clone, Copy, PartialEq, OdooSelection)]
+//! enum LabelState {
+//!     #[odoo(key = "draft", label = "Draft")]
+//!     Draft,
+//!     #[odoo(key = "in_use", label = "In use")]
+//!     InUse,
+//! }
+//! ```
+use serde_json::Value;
+
+use crate::FieldDescriptor;
+
+/// Rust enum mapped to the keys of a selection field, see `#[derive(OdooSelection)]`
+pub trait Selection: Sized + Copy + 'static {
+    /// every value, in declaration order
+    const VALUES: &'static [Self];
+
+    /// key stored by the server (`in_use`)
+    fn key(&self) -> &'static str;
+    /// label to display (`In use`)
+    fn label(&self) -> &'static str;
+
+    /// value of `key`, if any
+    fn from_key(key: &str) -> Option<Self> {
+        Self::VALUES
+            .iter()
+            .find(|value| value.key() == key)
+            .copied()
+    }
+}
+
+impl FieldDescriptor {
+    /// keys of a selection field
+    pub fn selection_keys(&self) -> Vec<&str> {
+        self.selection.iter().map(|(key, _)| key.as_str()).collect()
+    }
+    /// label of selection key `key`, as translated by the server
+    pub fn selection_label(&self, key: &str) -> Option<&str> {
+        self.selection
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, label)| label.as_str())
+    }
+    /// is `value` accepted by this selection field? (`false` clears it)
+    pub(crate) fn accepts(&self, value: &Value) -> bool {
+        match value {
+            Value::Bool(false) | Value::Null => true,
+            Value::String(key) => self.selection.iter().any(|(k, _)| k == key),
+            Value::Number(n) => self.selection.iter().any(|(k, _)| *k == n.to_string()),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Selection;
+    use crate::{FieldDescriptor, ObjectDescriptor};
+    use serde_json::json;
+
+    #[derive(Debug, Clone, Copy, PartialEq, crate::OdooSelection)]
+    enum LabelState {
+        Draft,
+        #[odoo(label = "In use")]
+        InUse,
+        #[odoo(key = "scrap", label = "Scrapped")]
+        Scrapped,
+    }
+
+    fn descriptor() -> ObjectDescriptor {
+        let state = FieldDescriptor {
+            type_: "selection".to_owned(),
+            selection: vec![
+                ("draft".to_owned(), "Draft".to_owned()),
+                ("in_use".to_owned(), "In use".to_owned()),
+            ],
+            ..Default::default()
+        };
+        ObjectDescriptor {
+            name: "stock.label".to_owned(),
+            fields: vec![("state".to_owned(), state)].into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn test_selection_enum() {
+        assert_eq!(LabelState::InUse.key(), "in_use");
+        assert_eq!(LabelState::InUse.label(), "In use");
+        assert_eq!(LabelState::Draft.label(), "Draft");
+        assert_eq!(LabelState::from_key("scrap"), Some(LabelState::Scrapped));
+        assert_eq!(LabelState::from_key("Draft"), None);
+        assert_eq!(LabelState::VALUES.len(), 3);
+    }
+
+    #[test]
+    fn test_selection_keys() {
+        let desc = descriptor();
+        let state = &desc.fields["state"];
+        assert_eq!(state.selection_keys(), vec!["draft", "in_use"]);
+        assert_eq!(state.selection_label("in_use"), Some("In use"));
+        assert!(state.accepts(&json!("draft")));
+        assert!(state.accepts(&json!(false)));
+        assert!(!state.accepts(&json!("Draft")));
+    }
+}
@@ -0,0 +1,424 @@
+//! serde helpers for Odoo values
+//!
+//! Records read from the server use `false` for empty values, `[id, "name"]`
+//! for many2one fields, id lists for x2many fields and strings for dates.
+//! The wrapper types and `deserialize_with` functions of this module map them
+//! to plain Rust types, see [`RecordSet::deserialize`]:
+//!
+//! ```ignore
+//! use roudoudou::serde::{Many2one, OdooDate, OdooOption};
+//!
+//! #[derive(Deserialize)]
+//! struct Label {
+//!     id: u32,
+//!     name: String,
+//!     product_id: OdooOption<Many2one>,
+//!     #[serde(deserialize_with = "roudoudou::serde::many2one_id")]
+//!     location_id: u32,
+//!     product_tag_ids: Vec<u32>,
+//!     printed_on: OdooOption<OdooDate>,
+//!     #[serde(deserialize_with = "roudoudou::serde::false_as_none")]
+//!     note: Option<String>,
+//! }
+//!
+//! let labels: Vec<Label> = cli.get_model("stock.label")?.search_browse(json!([]))?.deserialize()?;
+//! ```
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::de::{self, DeserializeOwned, Deserializer, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::ops::Deref;
+
+use crate::datetime::{parse_date, parse_datetime, DATETIME_FORMAT, DATE_FORMAT};
+use crate::{RecordSet, Result, ResultExt};
+
+/// value of a field where `false` (or `null`) means empty
+///
+/// Serialized back as `false` when empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OdooOption<T>(pub Option<T>);
+
+impl<T> OdooOption<T> {
+    pub fn into_option(self) -> Option<T> {
+        self.0
+    }
+}
+
+impl<T> Default for OdooOption<T> {
+    fn default() -> Self {
+        OdooOption(None)
+    }
+}
+
+impl<T> Deref for OdooOption<T> {
+    type Target = Option<T>;
+    fn deref(&self) -> &Option<T> {
+        &self.0
+    }
+}
+
+impl<T> From<Option<T>> for OdooOption<T> {
+    fn from(value: Option<T>) -> Self {
+        OdooOption(value)
+    }
+}
+
+impl<T> From<OdooOption<T>> for Option<T> {
+    fn from(value: OdooOption<T>) -> Self {
+        value.0
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for OdooOption<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        false_as_none(deserializer).map(OdooOption)
+    }
+}
+
+impl<T: Serialize> Serialize for OdooOption<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match &self.0 {
+            Some(value) => value.serialize(serializer),
+            None => serializer.serialize_bool(false),
+        }
+    }
+}
+
+/// many2one value read as `[id, "name"]`, serialized as its id
+///
+/// Use [`OdooOption<Many2one>`](OdooOption) for fields that may be empty.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Many2one {
+    pub id: u32,
+    pub name: String,
+}
+
+impl<'de> Deserialize<'de> for Many2one {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Pair(u32, String),
+            Id(u32),
+        }
+        match Repr::deserialize(deserializer) {
+            Ok(Repr::Pair(id, name)) => Ok(Many2one { id, name }),
+            Ok(Repr::Id(id)) => Ok(Many2one {
+                id,
+                name: String::new(),
+            }),
+            Err(_) => Err(de::Error::custom(
+                "expected a many2one value, [id, \"name\"] or id",
+            )),
+        }
+    }
+}
+
+impl Serialize for Many2one {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u32(self.id)
+    }
+}
+
+/// date field, serialized with the server format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OdooDate(pub NaiveDate);
+
+impl Deref for OdooDate {
+    type Target = NaiveDate;
+    fn deref(&self) -> &NaiveDate {
+        &self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for OdooDate {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        date(deserializer).map(OdooDate)
+    }
+}
+
+impl Serialize for OdooDate {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&self.0.format(DATE_FORMAT))
+    }
+}
+
+/// naive UTC datetime field, serialized with the server format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OdooDatetime(pub NaiveDateTime);
+
+impl Deref for OdooDatetime {
+    type Target = NaiveDateTime;
+    fn deref(&self) -> &NaiveDateTime {
+        &self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for OdooDatetime {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        datetime(deserializer).map(OdooDatetime)
+    }
+}
+
+impl Serialize for OdooDatetime {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&self.0.format(DATETIME_FORMAT))
+    }
+}
+
+/// `deserialize_with` for `Option<T>` fields: `false` and `null` are `None`
+pub fn false_as_none<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Null | Value::Bool(false) => Ok(None),
+        value => T::deserialize(value.into_deserializer())
+            .map(Some)
+            .map_err(de::Error::custom),
+    }
+}
+
+/// `deserialize_with` for `u32` fields holding a many2one id
+pub fn many2one_id<'de, D>(deserializer: D) -> std::result::Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Many2one::deserialize(deserializer).map(|many2one| many2one.id)
+}
+
+/// `deserialize_with` for `Vec<u32>` x2many fields, `false` is an empty list
+pub fn ids<'de, D>(deserializer: D) -> std::result::Result<Vec<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    false_as_none(deserializer).map(Option::unwrap_or_default)
+}
+
+/// `deserialize_with` for `String` fields, `false` is an empty string
+pub fn string<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    false_as_none(deserializer).map(Option::unwrap_or_default)
+}
+
+struct DateVisitor<T> {
+    expected: &'static str,
+    parse: fn(&str) -> Option<T>,
+}
+
+impl<'de, T> Visitor<'de> for DateVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expected)
+    }
+    fn visit_str<E: de::Error>(self, text: &str) -> std::result::Result<T, E> {
+        (self.parse)(text).ok_or_else(|| E::invalid_value(de::Unexpected::Str(text), &self))
+    }
+}
+
+/// `deserialize_with` for `NaiveDate` fields
+pub fn date<'de, D>(deserializer: D) -> std::result::Result<NaiveDate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(DateVisitor {
+        expected: "a date (YYYY-MM-DD)",
+        parse: parse_date,
+    })
+}
+
+/// `deserialize_with` for `NaiveDateTime` fields (naive UTC)
+pub fn datetime<'de, D>(deserializer: D) -> std::result::Result<NaiveDateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(DateVisitor {
+        expected: "a datetime (YYYY-MM-DD HH:MM:SS)",
+        parse: parse_datetime,
+    })
+}
+
+/// deserializer capturing the field names of a struct, it deserializes nothing
+struct FieldNames<'f>(&'f mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> std::result::Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("field names only"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// field names of struct `T`, empty if `T` is not a struct
+pub(crate) fn field_names<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+impl RecordSet<'_> {
+    /// deserialize every record of this set into a `T`
+    ///
+    /// The fields of `T`, when it is a struct, are loaded first. Use the
+    /// helpers of [`crate::serde`] for many2one, dates and empty values.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        let names: Vec<&str> = field_names::<T>()
+            .iter()
+            .copied()
+            .filter(|name| *name != "id" && self.model.desc.fields.contains_key(*name))
+            .collect();
+        if !names.is_empty() {
+            self.prefetch(&names)?;
+        }
+        let data = self.data.borrow();
+        self.ids
+            .iter()
+            .map(|id| {
+                let mut row = data.get(id).cloned().unwrap_or_else(Map::new);
+                row.insert("id".to_owned(), Value::from(*id));
+                serde_json::from_value(Value::Object(row))
+                    .chain_err(|| format!("could not deserialize {},{}", self.model.name(), id))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{field_names, Many2one, OdooDate, OdooOption};
+    use chrono::NaiveDate;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize)]
+    struct Label {
+        id: u32,
+        product_id: OdooOption<Many2one>,
+        #[serde(deserialize_with = "super::many2one_id")]
+        location_id: u32,
+        #[serde(deserialize_with = "super::ids")]
+        product_tag_ids: Vec<u32>,
+        printed_on: OdooOption<OdooDate>,
+        #[serde(deserialize_with = "super::false_as_none")]
+        note: Option<String>,
+        #[serde(deserialize_with = "super::string")]
+        name: String,
+    }
+
+    #[test]
+    fn test_deserialize_record() {
+        let label: Label = serde_json::from_value(json!({
+            "id": 7,
+            "product_id": [3, "Apple"],
+            "location_id": [12, "WH/Stock"],
+            "product_tag_ids": false,
+            "printed_on": "2021-03-01",
+            "note": false,
+            "name": false,
+        }))
+        .unwrap();
+        assert_eq!(label.id, 7);
+        assert_eq!(
+            label
+                .product_id
+                .as_ref()
+                .map(|product| product.name.as_str()),
+            Some("Apple")
+        );
+        assert_eq!(label.location_id, 12);
+        assert!(label.product_tag_ids.is_empty());
+        assert_eq!(
+            label.printed_on.map(|date| date.0),
+            Some(NaiveDate::from_ymd_opt(2021, 3, 1).unwrap())
+        );
+        assert_eq!(label.note, None);
+        assert_eq!(label.name, "");
+
+        let label: Label = serde_json::from_value(json!({
+            "id": 7,
+            "product_id": false,
+            "location_id": 12,
+            "product_tag_ids": [1, 2],
+            "printed_on": false,
+            "note": "fragile",
+            "name": "L-0001",
+        }))
+        .unwrap();
+        assert_eq!(*label.product_id, None);
+        assert_eq!(label.product_tag_ids, vec![1, 2]);
+        assert_eq!(label.note.as_deref(), Some("fragile"));
+
+        assert!(serde_json::from_value::<OdooDate>(json!("01/03/2021")).is_err());
+        assert_eq!(
+            serde_json::to_value(OdooOption::<Many2one>(None)).unwrap(),
+            json!(false)
+        );
+        assert_eq!(
+            serde_json::to_value(OdooDate(NaiveDate::from_ymd_opt(2021, 3, 1).unwrap())).unwrap(),
+            json!("2021-03-01")
+        );
+    }
+
+    #[test]
+    fn test_field_names() {
+        assert_eq!(
+            field_names::<Label>(),
+            &[
+                "id",
+                "product_id",
+                "location_id",
+                "product_tag_ids",
+                "printed_on",
+                "note",
+                "name"
+            ]
+        );
+        assert!(field_names::<Vec<u32>>().is_empty());
+    }
+}
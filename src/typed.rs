@@ -0,0 +1,655 @@
+//! typed models, see `#[derive(OdooModel)]`
+//!
+//! ```ignore
+//! #[derive(OdooModel)]
+//! #[odoo(model = "stock.label")]
+//! struct Label {
+//!     id: u32,
+//!     name: String,
+//!     #[odoo(rename = "is_terminal")]
+//!     terminal: bool,
+//!     product_id: Option<Many2one<Product>>,
+//! }
+//!
+//! let labels = cli.typed_model::<Label>()?;
+//! for label in labels.search(json!([("is_terminal", "=", true)]))? {
+//!     println!("{} {:?}", label.name, label.product_id);
+//! }
+//! ```
+//!
+//! Unset values are sent as `false` by the server, use `Option<T>` for
+//! any field which may be empty.
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde_json::{json, Map, Value};
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+
+use crate::datetime::{to_date_value, to_datetime_value, DATETIME_FORMAT};
+use crate::{
+    Command, Error, ErrorKind, IntoDomain, Model, ObjectDescriptor, OdooClient, OdooValue, Record,
+    Result,
+};
+
+/// values passed to `create` and `write`
+pub type Values = Map<String, Value>;
+
+/// Odoo field mapped by a struct field
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSpec {
+    /// Odoo field name
+    pub name: &'static str,
+    /// compatible field types, any type if empty
+    pub kinds: &'static [&'static str],
+    /// comodel of relational fields
+    pub relation: Option<&'static str>,
+    /// keys expected on selection fields
+    pub keys: Vec<&'static str>,
+}
+
+/// Rust type of a struct field mapped to an Odoo field
+pub trait FieldValue: Sized {
+    /// compatible field types, any type if empty
+    fn kinds() -> &'static [&'static str];
+    /// comodel of relational values
+    fn relation() -> Option<&'static str> {
+        None
+    }
+    /// selection keys, see `Selection`
+    fn keys() -> Vec<&'static str> {
+        Vec::new()
+    }
+    /// decode a value read from the server, `None` if it does not fit
+    fn from_odoo(value: OdooValue) -> Option<Self>;
+    /// value as written by `create` and `write`
+    fn to_odoo(&self) -> Value;
+}
+
+/// struct mapped to an Odoo model, implemented by `#[derive(OdooModel)]`
+pub trait OdooModel: Sized {
+    /// model name (`stock.label`)
+    const MODEL: &'static str;
+
+    /// mapped fields, without `id`
+    fn fields() -> Vec<FieldSpec>;
+    /// record id, `None` if the struct has no `id` field
+    fn id(&self) -> Option<u32>;
+    /// build from a record with all mapped fields loaded
+    fn from_record(record: &Record<'_, '_>) -> Result<Self>;
+    /// mapped fields, as written by `create` and `write`
+    fn to_values(&self) -> Values;
+
+    /// names of the mapped fields
+    fn field_names() -> Vec<&'static str> {
+        Self::fields().iter().map(|field| field.name).collect()
+    }
+    /// check that the mapped fields exist on the server, with compatible types
+    fn check(desc: &ObjectDescriptor) -> Result<()> {
+        let mut problems = Vec::new();
+        for spec in Self::fields() {
+            let field = match desc.fields.get(spec.name) {
+                Some(field) => field,
+                None => {
+                    problems.push(format!("no field {}", spec.name));
+                    continue;
+                }
+            };
+            if !spec.kinds.is_empty() && !spec.kinds.contains(&field.type_.as_str()) {
+                problems.push(format!(
+                    "{} is a {} field, expected {}",
+                    spec.name,
+                    field.type_,
+                    spec.kinds.join(" or ")
+                ));
+            }
+            let unknown: Vec<&str> = spec
+                .keys
+                .iter()
+                .filter(|key| !field.selection.iter().any(|(k, _)| k == *key))
+                .cloned()
+                .collect();
+            if !unknown.is_empty() && !field.selection.is_empty() {
+                problems.push(format!("{} has no value {}", spec.name, unknown.join(", ")));
+            }
+            if let Some(relation) = spec.relation {
+                if field.relation.as_deref() != Some(relation) {
+                    problems.push(format!(
+                        "{} links to {}, expected {}",
+                        spec.name,
+                        field.relation.as_deref().unwrap_or("nothing"),
+                        relation
+                    ));
+                }
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::from_kind(ErrorKind::SchemaMismatch(
+                Self::MODEL.to_owned(),
+                problems,
+            )))
+        }
+    }
+}
+
+/// decode field `name` of `record`, used by `#[derive(OdooModel)]`
+pub fn read_field<T: FieldValue>(record: &Record<'_, '_>, name: &str) -> Result<T> {
+    let value = record.value(name).ok_or_else(|| {
+        Error::from_kind(ErrorKind::InvalidField(format!(
+            "{} is not loaded for record {}",
+            name,
+            record.id()
+        )))
+    })?;
+    T::from_odoo(value.clone()).ok_or_else(|| {
+        Error::from_kind(ErrorKind::InvalidField(format!(
+            "unexpected value for {}: {:?}",
+            name, value
+        )))
+    })
+}
+
+/// typed access to the model of `M`
+pub struct TypedModel<'a, M> {
+    model: Model<'a>,
+    marker: PhantomData<M>,
+}
+
+impl<'a, M: OdooModel> TypedModel<'a, M> {
+    /// the untyped model
+    pub fn model(&self) -> &Model<'a> {
+        &self.model
+    }
+    /// records matching `domain`
+    pub fn search<D: IntoDomain>(&self, domain: D) -> Result<Vec<M>> {
+        let ids = self.model.search(domain)?;
+        self.browse(&ids)
+    }
+    /// records `ids`, read at once
+    pub fn browse(&self, ids: &[u32]) -> Result<Vec<M>> {
+        let records = self.model.browse_fields(ids, &M::field_names())?;
+        records
+            .iter()
+            .map(|record| M::from_record(&record))
+            .collect()
+    }
+    /// create a record from `record` (its id is ignored)
+    ///
+    /// Fields readonly on the server are not sent.
+    pub fn create(&self, record: &M) -> Result<u32> {
+        self.model.create(Value::Object(writable(
+            &self.model.desc,
+            record.to_values(),
+        )))
+    }
+    /// write every mapped field of `record`, except those readonly on the server
+    pub fn write(&self, record: &M) -> Result<bool> {
+        let id = record.id().ok_or_else(|| {
+            Error::from_kind(ErrorKind::InvalidField(format!(
+                "{} is mapped without an id field",
+                M::MODEL
+            )))
+        })?;
+        self.model.browse_lazy(&[id]).write(Value::Object(writable(
+            &self.model.desc,
+            record.to_values(),
+        )))
+    }
+}
+
+/// `values` without the fields readonly on the server (computed fields among them)
+fn writable(desc: &ObjectDescriptor, values: Values) -> Values {
+    values
+        .into_iter()
+        .filter(
+            |(name, _)| !matches!(desc.fields.get(name), Some(field) if field.always_readonly()),
+        )
+        .collect()
+}
+
+impl OdooClient {
+    /// typed model of `M`, checked against the server descriptor
+    pub fn typed_model<M: OdooModel>(&self) -> Result<TypedModel<'_, M>> {
+        let model = self.get_model(M::MODEL)?;
+        M::check(&model.desc)?;
+        Ok(TypedModel {
+            model,
+            marker: PhantomData,
+        })
+    }
+}
+
+/// many2one value linking to the model of `M`
+pub struct Many2one<M> {
+    pub id: u32,
+    /// display name, empty for values built on the client side
+    pub name: String,
+    marker: PhantomData<M>,
+}
+
+impl<M> Many2one<M> {
+    pub fn new(id: u32) -> Self {
+        Many2one {
+            id,
+            name: String::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M: OdooModel> Many2one<M> {
+    /// read the linked record
+    pub fn get(&self, cli: &OdooClient) -> Result<M> {
+        cli.typed_model::<M>()?
+            .browse(&[self.id])?
+            .pop()
+            .ok_or_else(|| {
+                Error::from_kind(ErrorKind::InvalidField(format!(
+                    "{},{} does not exist",
+                    M::MODEL,
+                    self.id
+                )))
+            })
+    }
+}
+
+impl<M> Clone for Many2one<M> {
+    fn clone(&self) -> Self {
+        Many2one {
+            id: self.id,
+            name: self.name.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M> PartialEq for Many2one<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<M> std::fmt::Debug for Many2one<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Many2one")
+            .field(&self.id)
+            .field(&self.name)
+            .finish()
+    }
+}
+
+/// one2many/many2many value linking to the model of `M`
+pub struct X2many<M> {
+    pub ids: Vec<u32>,
+    marker: PhantomData<M>,
+}
+
+impl<M> X2many<M> {
+    pub fn new(ids: Vec<u32>) -> Self {
+        X2many {
+            ids,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M: OdooModel> X2many<M> {
+    /// read the linked records
+    pub fn get(&self, cli: &OdooClient) -> Result<Vec<M>> {
+        cli.typed_model::<M>()?.browse(&self.ids)
+    }
+}
+
+impl<M> Clone for X2many<M> {
+    fn clone(&self) -> Self {
+        X2many::new(self.ids.clone())
+    }
+}
+
+impl<M> PartialEq for X2many<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ids == other.ids
+    }
+}
+
+impl<M> std::fmt::Debug for X2many<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("X2many").field(&self.ids).finish()
+    }
+}
+
+impl<M: OdooModel> FieldValue for Many2one<M> {
+    fn kinds() -> &'static [&'static str] {
+        &["many2one"]
+    }
+    fn relation() -> Option<&'static str> {
+        Some(M::MODEL)
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        match value {
+            OdooValue::Many2one(id, name) => Some(Many2one {
+                id,
+                name,
+                marker: PhantomData,
+            }),
+            _ => None,
+        }
+    }
+    fn to_odoo(&self) -> Value {
+        json!(self.id)
+    }
+}
+
+impl<M: OdooModel> FieldValue for X2many<M> {
+    fn kinds() -> &'static [&'static str] {
+        &["one2many", "many2many"]
+    }
+    fn relation() -> Option<&'static str> {
+        Some(M::MODEL)
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        match value {
+            OdooValue::X2many(ids) => Some(X2many::new(ids)),
+            _ => None,
+        }
+    }
+    fn to_odoo(&self) -> Value {
+        json!([Command::Set(self.ids.clone())])
+    }
+}
+
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn kinds() -> &'static [&'static str] {
+        T::kinds()
+    }
+    fn relation() -> Option<&'static str> {
+        T::relation()
+    }
+    fn keys() -> Vec<&'static str> {
+        T::keys()
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        match value {
+            OdooValue::Null => Some(None),
+            value => T::from_odoo(value).map(Some),
+        }
+    }
+    fn to_odoo(&self) -> Value {
+        match self {
+            Some(value) => value.to_odoo(),
+            None => Value::Bool(false),
+        }
+    }
+}
+
+impl FieldValue for OdooValue {
+    fn kinds() -> &'static [&'static str] {
+        &[]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        Some(value)
+    }
+    fn to_odoo(&self) -> Value {
+        self.to_write()
+    }
+}
+
+impl FieldValue for bool {
+    fn kinds() -> &'static [&'static str] {
+        &["boolean"]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        value.as_bool()
+    }
+    fn to_odoo(&self) -> Value {
+        json!(self)
+    }
+}
+
+impl FieldValue for i64 {
+    fn kinds() -> &'static [&'static str] {
+        &["integer"]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        value.as_i64()
+    }
+    fn to_odoo(&self) -> Value {
+        json!(self)
+    }
+}
+
+impl FieldValue for i32 {
+    fn kinds() -> &'static [&'static str] {
+        &["integer"]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        value.as_i64().and_then(|i| i32::try_from(i).ok())
+    }
+    fn to_odoo(&self) -> Value {
+        json!(self)
+    }
+}
+
+impl FieldValue for u32 {
+    fn kinds() -> &'static [&'static str] {
+        &["integer"]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        value.as_i64().and_then(|i| u32::try_from(i).ok())
+    }
+    fn to_odoo(&self) -> Value {
+        json!(self)
+    }
+}
+
+impl FieldValue for f64 {
+    fn kinds() -> &'static [&'static str] {
+        &["float", "monetary", "integer"]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        value.as_f64()
+    }
+    fn to_odoo(&self) -> Value {
+        json!(self)
+    }
+}
+
+impl FieldValue for String {
+    fn kinds() -> &'static [&'static str] {
+        &["char", "text", "html", "selection"]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        value.as_str().map(|s| s.to_owned())
+    }
+    fn to_odoo(&self) -> Value {
+        json!(self)
+    }
+}
+
+impl FieldValue for NaiveDate {
+    fn kinds() -> &'static [&'static str] {
+        &["date"]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        value.as_date()
+    }
+    fn to_odoo(&self) -> Value {
+        to_date_value(self)
+    }
+}
+
+impl FieldValue for NaiveDateTime {
+    fn kinds() -> &'static [&'static str] {
+        &["datetime"]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        value.as_datetime().map(|datetime| datetime.naive_utc())
+    }
+    fn to_odoo(&self) -> Value {
+        json!(self.format(DATETIME_FORMAT).to_string())
+    }
+}
+
+impl FieldValue for DateTime<Utc> {
+    fn kinds() -> &'static [&'static str] {
+        &["datetime"]
+    }
+    fn from_odoo(value: OdooValue) -> Option<Self> {
+        value.as_datetime()
+    }
+    fn to_odoo(&self) -> Value {
+        to_datetime_value(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{writable, Many2one, OdooModel, X2many};
+    use crate::tests::{offline_client, offline_model, offline_records};
+    use crate::{FieldDescriptor, ObjectDescriptor};
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, crate::OdooModel)]
+    #[odoo(model = "product.product")]
+    struct Product {
+        id: u32,
+        name: String,
+    }
+
+    #[derive(Debug, PartialEq, crate::OdooModel)]
+    #[odoo(model = "stock.label")]
+    struct Label {
+        id: u32,
+        name: String,
+        #[odoo(rename = "is_terminal")]
+        terminal: bool,
+        product_id: Option<Many2one<Product>>,
+        product_tag_ids: X2many<Product>,
+        state: LabelState,
+        #[odoo(skip)]
+        printed: bool,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, crate::OdooSelection)]
+    enum LabelState {
+        Draft,
+        InUse,
+    }
+
+    #[test]
+    fn test_from_record() {
+        let cli = offline_client();
+        let model = offline_model(
+            &cli,
+            "stock.label",
+            &[
+                ("name", "char"),
+                ("is_terminal", "boolean"),
+                ("product_id", "many2one"),
+                ("product_tag_ids", "many2many"),
+                ("state", "selection"),
+            ],
+        );
+        let labels = offline_records(
+            &model,
+            json!([
+                {"id": 1, "name": "A", "is_terminal": true, "product_id": [3, "Handset"], "product_tag_ids": [4], "state": "in_use"},
+                {"id": 2, "name": "B", "is_terminal": false, "product_id": false, "product_tag_ids": [], "state": "draft"}
+            ]),
+        );
+        let label = Label::from_record(&labels.record(0).unwrap()).unwrap();
+        assert_eq!(label.id, 1);
+        assert!(label.terminal && !label.printed);
+        assert_eq!(label.product_id.as_ref().map(|p| p.id), Some(3));
+        assert_eq!(label.product_id.as_ref().unwrap().name, "Handset");
+        assert_eq!(label.state, LabelState::InUse);
+        assert_eq!(
+            json!(label.to_values()),
+            json!({
+                "name": "A",
+                "is_terminal": true,
+                "product_id": 3,
+                "product_tag_ids": [[6, 0, [4]]],
+                "state": "in_use"
+            })
+        );
+        let label = Label::from_record(&labels.record(1).unwrap()).unwrap();
+        assert_eq!(label.product_id, None);
+        assert_eq!(label.id(), Some(2));
+
+        let names = offline_records(&model, json!([{"id": 3, "name": false}]));
+        assert!(Label::from_record(&names.record(0).unwrap()).is_err());
+
+        let desc = ObjectDescriptor {
+            name: "stock.label".to_owned(),
+            fields: vec![(
+                "name".to_owned(),
+                FieldDescriptor {
+                    type_: "char".to_owned(),
+                    readonly: true,
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+        };
+        let values = writable(&desc, label.to_values());
+        assert!(!values.contains_key("name"));
+        assert!(values.contains_key("state"));
+    }
+
+    #[test]
+    fn test_check_descriptor() {
+        let field = |type_: &str, relation: Option<&str>| FieldDescriptor {
+            type_: type_.to_owned(),
+            relation: relation.map(|r| r.to_owned()),
+            ..Default::default()
+        };
+        let mut desc = ObjectDescriptor {
+            name: "stock.label".to_owned(),
+            fields: vec![
+                ("name".to_owned(), field("char", None)),
+                ("is_terminal".to_owned(), field("boolean", None)),
+                (
+                    "product_id".to_owned(),
+                    field("many2one", Some("product.product")),
+                ),
+                (
+                    "product_tag_ids".to_owned(),
+                    field("many2many", Some("product.product")),
+                ),
+                (
+                    "state".to_owned(),
+                    FieldDescriptor {
+                        selection: vec![
+                            ("draft".to_owned(), "Draft".to_owned()),
+                            ("in_use".to_owned(), "In use".to_owned()),
+                        ],
+                        ..field("selection", None)
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        assert!(Label::check(&desc).is_ok());
+
+        desc.fields
+            .insert("is_terminal".to_owned(), field("char", None));
+        desc.fields.remove("name");
+        desc.fields.get_mut("state").unwrap().selection.truncate(1);
+        desc.fields.insert(
+            "product_id".to_owned(),
+            field("many2one", Some("product.template")),
+        );
+        let err = Label::check(&desc).unwrap_err().to_string();
+        assert!(err.contains("no field name"), "{}", err);
+        assert!(err.contains("is_terminal is a char field"), "{}", err);
+        assert!(err.contains("state has no value in_use"), "{}", err);
+        assert!(
+            err.contains("product_id links to product.template"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! client side validation of `create` and `write` values
+//!
+//! Values are checked against `fields_get` before being sent, every problem
+//! is reported at once in an [`ErrorKind::Validation`] error:
+//!
+//! ```ignore
+//! labels.create(json!({"name": 3, "state": "drfat"}))?;
+//! // invalid values for stock.label: name expects a string, got 3; state expects one of ...
+//! labels.create_unchecked(json!({"name": "L-0001"}))?;
+//! ```
+use serde_json::{Map, Value};
+use std::fmt;
+
+use crate::datetime::{parse_date, parse_datetime};
+use crate::{Command, Error, ErrorKind, FieldDescriptor, ObjectDescriptor, Result};
+
+/// what values are validated for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValidationMode {
+    /// new record: required fields must be set
+    Create,
+    /// existing records: only the given fields are checked
+    Write,
+}
+
+/// one problem found by [`ObjectDescriptor::validate`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// the model has no such field
+    UnknownField(String),
+    /// the field can not be assigned
+    Readonly(String),
+    /// a required field is missing, or cleared
+    MissingRequired(String),
+    /// the value does not fit the field type
+    TypeMismatch {
+        field: String,
+        expected: String,
+        value: Value,
+    },
+}
+
+impl Violation {
+    /// name of the field at fault
+    pub fn field(&self) -> &str {
+        match self {
+            Violation::UnknownField(field)
+            | Violation::Readonly(field)
+            | Violation::MissingRequired(field)
+            | Violation::TypeMismatch { field, .. } => field,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::UnknownField(field) => write!(f, "unknown field {}", field),
+            Violation::Readonly(field) => write!(f, "{} is readonly", field),
+            Violation::MissingRequired(field) => write!(f, "{} is required", field),
+            Violation::TypeMismatch {
+                field,
+                expected,
+                value,
+            } => write!(f, "{} expects {}, got {}", field, expected, value),
+        }
+    }
+}
+
+impl FieldDescriptor {
+    /// readonly whatever the record state
+    ///
+    /// `states` may make a readonly field writable depending on the record.
+    pub(crate) fn always_readonly(&self) -> bool {
+        let has_states = matches!(self.states.as_object(), Some(states) if !states.is_empty());
+        self.readonly && !has_states
+    }
+    /// computed from other fields (`fields_get` sends their `depends`)
+    pub(crate) fn is_computed(&self) -> bool {
+        !self.depends.is_empty()
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// a database id, as sent for many2one values
+pub(crate) fn is_id(value: &Value) -> bool {
+    matches!(value.as_u64(), Some(id) if id > 0 && id <= u64::from(u32::MAX))
+}
+
+fn is_x2many_item(value: &Value) -> bool {
+    is_id(value)
+        || Command::is_command(value) && serde_json::from_value::<Command>(value.to_owned()).is_ok()
+}
+
+/// what field `field` expects, if `value` does not fit it
+fn mismatch(field: &FieldDescriptor, value: &Value) -> Option<String> {
+    let (fits, expected) = match field.type_.as_str() {
+        "boolean" => (value.is_boolean() || value.is_null(), "a boolean"),
+        _ if is_empty(value) => (true, ""),
+        "integer" => (value.is_i64() || value.is_u64(), "an integer"),
+        "float" | "monetary" => match value {
+            Value::Number(_) => (true, ""),
+            Value::String(text) => (text.trim().parse::<f64>().is_ok(), "a number"),
+            _ => (false, "a number"),
+        },
+        "char" | "text" | "html" | "binary" => (value.is_string(), "a string"),
+        "date" => (
+            value.as_str().and_then(parse_date).is_some(),
+            "a date (YYYY-MM-DD)",
+        ),
+        "datetime" => (
+            value.as_str().and_then(parse_datetime).is_some(),
+            "a datetime (YYYY-MM-DD HH:MM:SS)",
+        ),
+        "many2one" => (is_id(value), "a record id"),
+        "one2many" | "many2many" => match value {
+            Value::Array(items) => (
+                items.iter().all(is_x2many_item),
+                "a list of x2many commands",
+            ),
+            _ => (false, "a list of x2many commands"),
+        },
+        "selection" if !field.selection.is_empty() => {
+            if field.accepts(value) {
+                return None;
+            }
+            return Some(format!("one of {}", field.selection_keys().join(", ")));
+        }
+        _ => (true, ""),
+    };
+    if fits {
+        None
+    } else {
+        Some(expected.to_owned())
+    }
+}
+
+impl ObjectDescriptor {
+    /// check `vals` before they are sent to `create` or `write`
+    ///
+    /// Unknown fields, readonly fields, values not fitting their field type
+    /// and required fields left empty are all reported in one
+    /// [`ErrorKind::Validation`] error. Computed required fields need no
+    /// value.
+    pub fn validate(&self, vals: &Value, mode: ValidationMode) -> Result<()> {
+        self.validate_with_defaults(vals, mode, &Map::new())
+    }
+
+    /// [`ObjectDescriptor::validate`], `defaults` from `default_get` only count
+    /// as present: they are not checked like `vals`
+    pub(crate) fn validate_with_defaults(
+        &self,
+        vals: &Value,
+        mode: ValidationMode,
+        defaults: &Map<String, Value>,
+    ) -> Result<()> {
+        let empty = Map::new();
+        let vals = match vals {
+            Value::Object(vals) => vals,
+            Value::Null => &empty,
+            other => {
+                return Err(Error::from_kind(ErrorKind::MyOtherError(format!(
+                    "{} values must be an object, got {}",
+                    self.name, other
+                ))))
+            }
+        };
+        let mut violations = Vec::new();
+        for (name, value) in vals {
+            let field = match self.fields.get(name) {
+                Some(field) => field,
+                None => {
+                    violations.push(Violation::UnknownField(name.to_owned()));
+                    continue;
+                }
+            };
+            if field.always_readonly() {
+                violations.push(Violation::Readonly(name.to_owned()));
+            } else if field.required && field.type_ != "boolean" && is_empty(value) {
+                violations.push(Violation::MissingRequired(name.to_owned()));
+            } else if let Some(expected) = mismatch(field, value) {
+                violations.push(Violation::TypeMismatch {
+                    field: name.to_owned(),
+                    expected,
+                    value: value.to_owned(),
+                });
+            }
+        }
+        if mode == ValidationMode::Create {
+            for name in self.missing_required(vals) {
+                if defaults.contains_key(name) {
+                    continue;
+                }
+                violations.push(Violation::MissingRequired(name.to_owned()));
+            }
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::from_kind(ErrorKind::Validation(
+                self.name.to_owned(),
+                violations,
+            )))
+        }
+    }
+
+    /// required fields absent from `vals`, computed ones excepted
+    ///
+    /// `store` is not looked at: servers may leave it out of `fields_get`.
+    pub(crate) fn missing_required<'d>(&'d self, vals: &Map<String, Value>) -> Vec<&'d str> {
+        self.fields
+            .iter()
+            .filter(|(name, field)| {
+                field.required && !field.is_computed() && !vals.contains_key(*name)
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ValidationMode, Violation};
+    use crate::{Command, ErrorKind, FieldDescriptor, ObjectDescriptor};
+    use serde_json::json;
+
+    fn field(type_: &str) -> FieldDescriptor {
+        FieldDescriptor {
+            type_: type_.to_owned(),
+            store: true,
+            ..Default::default()
+        }
+    }
+
+    fn descriptor() -> ObjectDescriptor {
+        let fields = vec![
+            (
+                "name",
+                FieldDescriptor {
+                    required: true,
+                    ..field("char")
+                },
+            ),
+            ("qty", field("float")),
+            ("printed_on", field("date")),
+            ("product_id", field("many2one")),
+            ("product_tag_ids", field("many2many")),
+            (
+                "display_name",
+                FieldDescriptor {
+                    readonly: true,
+                    ..field("char")
+                },
+            ),
+            (
+                "state",
+                FieldDescriptor {
+                    selection: vec![("draft".to_owned(), "Draft".to_owned())],
+                    ..field("selection")
+                },
+            ),
+        ];
+        ObjectDescriptor {
+            name: "stock.label".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(name, field)| (name.to_owned(), field))
+                .collect(),
+        }
+    }
+
+    fn violations(vals: serde_json::Value, mode: ValidationMode) -> Vec<Violation> {
+        match descriptor().validate(&vals, mode) {
+            Ok(()) => vec![],
+            Err(err) => match err.0 {
+                ErrorKind::Validation(_, violations) => violations,
+                other => panic!("unexpected error {}", other),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let vals = json!({
+            "name": "L-0001",
+            "qty": "1.50",
+            "printed_on": "2021-03-01",
+            "product_id": 3,
+            "product_tag_ids": [Command::Link(3), 4],
+            "state": false,
+        });
+        assert_eq!(violations(vals, ValidationMode::Create), vec![]);
+        assert_eq!(violations(json!({"qty": 2}), ValidationMode::Write), vec![]);
+
+        let found = violations(
+            json!({
+                "nmae": "L-0001",
+                "display_name": "L-0001",
+                "qty": "many",
+                "printed_on": "01/03/2021",
+                "product_id": [3, "Apple"],
+                "product_tag_ids": [[7, 3]],
+                "state": "drfat",
+            }),
+            ValidationMode::Create,
+        );
+        let fields: Vec<&str> = found.iter().map(Violation::field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "display_name",
+                "nmae",
+                "printed_on",
+                "product_id",
+                "product_tag_ids",
+                "qty",
+                "state",
+                "name"
+            ]
+        );
+        assert_eq!(found[1], Violation::UnknownField("nmae".to_owned()));
+        assert_eq!(found[7], Violation::MissingRequired("name".to_owned()));
+        assert_eq!(
+            found[6].to_string(),
+            "state expects one of draft, got \"drfat\""
+        );
+
+        assert_eq!(
+            violations(json!({"name": false}), ValidationMode::Write),
+            vec![Violation::MissingRequired("name".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_validate_defaults() {
+        let mut desc = descriptor();
+        desc.fields.insert(
+            "company_id".to_owned(),
+            // `store` left out by the server
+            FieldDescriptor {
+                required: true,
+                readonly: true,
+                type_: "many2one".to_owned(),
+                ..Default::default()
+            },
+        );
+        desc.fields.insert(
+            "code".to_owned(),
+            FieldDescriptor {
+                required: true,
+                readonly: true,
+                depends: vec!["name".to_owned()],
+                ..field("char")
+            },
+        );
+        let defaults = json!({"company_id": [1, "YourCompany"]});
+        let defaults = defaults.as_object().unwrap();
+        let vals = json!({"name": "L-0001"});
+        assert!(desc
+            .validate_with_defaults(&vals, ValidationMode::Create, defaults)
+            .is_ok());
+        match desc.validate(&vals, ValidationMode::Create) {
+            Err(err) => match err.0 {
+                ErrorKind::Validation(_, violations) => assert_eq!(
+                    violations,
+                    vec![Violation::MissingRequired("company_id".to_owned())]
+                ),
+                other => panic!("unexpected error {}", other),
+            },
+            Ok(()) => panic!("company_id should be missing"),
+        }
+    }
+}
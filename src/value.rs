@@ -0,0 +1,326 @@
+//! typed field values
+//!
+//! Odoo sends `false` for most empty values (strings, dates, many2one...),
+//! `OdooValue` decodes raw JSON using the field type from `fields_get`.
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+
+use crate::datetime::{parse_date, parse_datetime, to_date_value, DATETIME_FORMAT};
+use crate::{Command, FieldDescriptor};
+
+/// value of a record field
+#[derive(Debug, Clone, PartialEq)]
+pub enum OdooValue {
+    /// empty value (`false` for non boolean fields)
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Char(String),
+    Text(String),
+    Html(String),
+    Date(NaiveDate),
+    /// naive UTC datetime (the server never sends the timezone)
+    Datetime(NaiveDateTime),
+    /// selection key
+    Selection(String),
+    /// many2one as `(id, display_name)`
+    Many2one(u32, String),
+    /// one2many/many2many ids
+    X2many(Vec<u32>),
+    /// base64 encoded content (or its size, if read with `bin_size`)
+    Binary(String),
+    Monetary(f64),
+    /// value of any other field type, kept as is
+    Raw(Value),
+}
+
+impl OdooValue {
+    /// decode `value` read from a field described by `desc`
+    pub fn decode(desc: &FieldDescriptor, value: &Value) -> OdooValue {
+        if desc.type_ == "boolean" {
+            return OdooValue::Bool(value.as_bool().unwrap_or(false));
+        }
+        if let Value::Bool(false) | Value::Null = value {
+            return match desc.type_.as_str() {
+                "one2many" | "many2many" => OdooValue::X2many(vec![]),
+                _ => OdooValue::Null,
+            };
+        }
+        let text = |value: &Value| match value {
+            Value::String(s) => s.to_owned(),
+            other => other.to_string(),
+        };
+        match (desc.type_.as_str(), value) {
+            ("integer", Value::Number(n)) => n
+                .as_i64()
+                .map(OdooValue::Integer)
+                .unwrap_or_else(|| OdooValue::Raw(value.to_owned())),
+            ("float", Value::Number(n)) => OdooValue::Float(n.as_f64().unwrap_or(0.0)),
+            ("monetary", Value::Number(n)) => OdooValue::Monetary(n.as_f64().unwrap_or(0.0)),
+            ("char", value) => OdooValue::Char(text(value)),
+            ("text", value) => OdooValue::Text(text(value)),
+            ("html", value) => OdooValue::Html(text(value)),
+            ("date", Value::String(s)) => parse_date(s)
+                .map(OdooValue::Date)
+                .unwrap_or_else(|| OdooValue::Raw(value.to_owned())),
+            ("datetime", Value::String(s)) => parse_datetime(s)
+                .map(OdooValue::Datetime)
+                .unwrap_or_else(|| OdooValue::Raw(value.to_owned())),
+            ("selection", value) => OdooValue::Selection(text(value)),
+            ("binary", value) => OdooValue::Binary(text(value)),
+            ("many2one", Value::Array(pair)) => match (pair.first(), pair.get(1)) {
+                (Some(Value::Number(id)), name) => OdooValue::Many2one(
+                    id.as_u64().unwrap_or(0) as u32,
+                    name.and_then(|n| n.as_str()).unwrap_or("").to_owned(),
+                ),
+                _ => OdooValue::Raw(value.to_owned()),
+            },
+            ("many2one", Value::Number(id)) => {
+                OdooValue::Many2one(id.as_u64().unwrap_or(0) as u32, String::new())
+            }
+            ("one2many", Value::Array(ids)) | ("many2many", Value::Array(ids)) => {
+                OdooValue::X2many(
+                    ids.iter()
+                        .filter_map(|id| id.as_u64())
+                        .map(|id| id as u32)
+                        .collect(),
+                )
+            }
+            (_, value) => OdooValue::Raw(value.to_owned()),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == OdooValue::Null
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            OdooValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            OdooValue::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+    /// numeric value of integer, float and monetary fields
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            OdooValue::Integer(i) => Some(*i as f64),
+            OdooValue::Float(f) | OdooValue::Monetary(f) => Some(*f),
+            _ => None,
+        }
+    }
+    /// text of char, text, html, selection and binary fields
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            OdooValue::Char(s)
+            | OdooValue::Text(s)
+            | OdooValue::Html(s)
+            | OdooValue::Selection(s)
+            | OdooValue::Binary(s) => Some(s),
+            _ => None,
+        }
+    }
+    pub fn as_date(&self) -> Option<NaiveDate> {
+        match self {
+            OdooValue::Date(date) => Some(*date),
+            _ => None,
+        }
+    }
+    /// datetime value, in UTC
+    pub fn as_datetime(&self) -> Option<DateTime<Utc>> {
+        match self {
+            OdooValue::Datetime(datetime) => Some(Utc.from_utc_datetime(datetime)),
+            _ => None,
+        }
+    }
+    /// datetime value converted to `tz` (see `OdooClient::user_tz`)
+    pub fn to_tz(&self, tz: Tz) -> Option<DateTime<Tz>> {
+        self.as_datetime()
+            .map(|datetime| datetime.with_timezone(&tz))
+    }
+    /// id of a many2one value
+    pub fn as_id(&self) -> Option<u32> {
+        match self {
+            OdooValue::Many2one(id, _) => Some(*id),
+            _ => None,
+        }
+    }
+    /// `(id, display_name)` of a many2one value
+    pub fn as_many2one(&self) -> Option<(u32, &str)> {
+        match self {
+            OdooValue::Many2one(id, name) => Some((*id, name)),
+            _ => None,
+        }
+    }
+    /// ids of a one2many/many2many value
+    pub fn as_ids(&self) -> Option<&[u32]> {
+        match self {
+            OdooValue::X2many(ids) => Some(ids),
+            _ => None,
+        }
+    }
+
+    /// JSON value, as read from the server
+    pub fn to_json(&self) -> Value {
+        match self {
+            OdooValue::Null => Value::Bool(false),
+            OdooValue::Bool(b) => json!(b),
+            OdooValue::Integer(i) => json!(i),
+            OdooValue::Float(f) | OdooValue::Monetary(f) => json!(f),
+            OdooValue::Char(s)
+            | OdooValue::Text(s)
+            | OdooValue::Html(s)
+            | OdooValue::Selection(s)
+            | OdooValue::Binary(s) => json!(s),
+            OdooValue::Date(date) => to_date_value(date),
+            OdooValue::Datetime(datetime) => json!(datetime.format(DATETIME_FORMAT).to_string()),
+            OdooValue::Many2one(id, name) => json!([id, name]),
+            OdooValue::X2many(ids) => json!(ids),
+            OdooValue::Raw(value) => value.to_owned(),
+        }
+    }
+    /// JSON value suitable for `create` and `write`
+    ///
+    /// many2one values are written as ids, x2many as a `Command::Set`.
+    pub fn to_write(&self) -> Value {
+        match self {
+            OdooValue::Many2one(id, _) => json!(id),
+            OdooValue::X2many(ids) => json!([Command::Set(ids.to_owned())]),
+            other => other.to_json(),
+        }
+    }
+}
+
+impl From<OdooValue> for Value {
+    fn from(value: OdooValue) -> Value {
+        value.to_write()
+    }
+}
+
+impl From<NaiveDate> for OdooValue {
+    fn from(date: NaiveDate) -> OdooValue {
+        OdooValue::Date(date)
+    }
+}
+
+impl<T: TimeZone> From<DateTime<T>> for OdooValue {
+    fn from(datetime: DateTime<T>) -> OdooValue {
+        OdooValue::Datetime(datetime.naive_utc())
+    }
+}
+
+/// serialized as written, so values can be used in `json!` vals and domains
+impl Serialize for OdooValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.to_write().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OdooValue;
+    use crate::FieldDescriptor;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use chrono_tz::Europe::Paris;
+    use serde_json::json;
+
+    fn field(type_: &str) -> FieldDescriptor {
+        FieldDescriptor {
+            type_: type_.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decode_false() {
+        assert_eq!(
+            OdooValue::decode(&field("char"), &json!(false)),
+            OdooValue::Null
+        );
+        assert_eq!(
+            OdooValue::decode(&field("date"), &json!(false)),
+            OdooValue::Null
+        );
+        assert_eq!(
+            OdooValue::decode(&field("many2one"), &json!(false)),
+            OdooValue::Null
+        );
+        assert_eq!(
+            OdooValue::decode(&field("boolean"), &json!(false)),
+            OdooValue::Bool(false)
+        );
+        assert_eq!(
+            OdooValue::decode(&field("many2many"), &json!(false)),
+            OdooValue::X2many(vec![])
+        );
+    }
+
+    #[test]
+    fn test_decode_values() {
+        let product = OdooValue::decode(&field("many2one"), &json!([3, "Handset"]));
+        assert_eq!(product, OdooValue::Many2one(3, "Handset".to_owned()));
+        assert_eq!(product.as_id(), Some(3));
+        assert_eq!(product.as_many2one(), Some((3, "Handset")));
+
+        let tags = OdooValue::decode(&field("many2many"), &json!([1, 2]));
+        assert_eq!(tags.as_ids(), Some(&[1, 2][..]));
+
+        let state = OdooValue::decode(&field("selection"), &json!("draft"));
+        assert_eq!(state, OdooValue::Selection("draft".to_owned()));
+        assert_eq!(state.as_str(), Some("draft"));
+
+        assert_eq!(
+            OdooValue::decode(&field("integer"), &json!(7)).as_f64(),
+            Some(7.0)
+        );
+    }
+
+    #[test]
+    fn test_decode_dates() {
+        let date = OdooValue::decode(&field("date"), &json!("2021-02-14"));
+        assert_eq!(date.as_date(), NaiveDate::from_ymd_opt(2021, 2, 14));
+        assert_eq!(date.to_json(), json!("2021-02-14"));
+
+        let datetime = OdooValue::decode(&field("datetime"), &json!("2021-02-14 10:00:00"));
+        assert_eq!(
+            datetime.as_datetime(),
+            Some(Utc.with_ymd_and_hms(2021, 2, 14, 10, 0, 0).unwrap())
+        );
+        assert_eq!(
+            datetime.to_tz(Paris).map(|d| d.to_rfc3339()),
+            Some("2021-02-14T11:00:00+01:00".to_owned())
+        );
+        assert_eq!(datetime.to_json(), json!("2021-02-14 10:00:00"));
+
+        let local = Paris.with_ymd_and_hms(2021, 7, 14, 12, 0, 0).unwrap();
+        assert_eq!(
+            json!({"date_done": OdooValue::from(local)}),
+            json!({"date_done": "2021-07-14 10:00:00"})
+        );
+        assert_eq!(
+            OdooValue::decode(&field("date"), &json!("soon")),
+            OdooValue::Raw(json!("soon"))
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(OdooValue::Null.to_json(), json!(false));
+        assert_eq!(
+            OdooValue::Many2one(3, "Handset".to_owned()).to_write(),
+            json!(3)
+        );
+        assert_eq!(
+            OdooValue::X2many(vec![1, 2]).to_write(),
+            json!([[6, 0, [1, 2]]])
+        );
+        assert_eq!(OdooValue::X2many(vec![1, 2]).to_json(), json!([1, 2]));
+    }
+}
@@ -0,0 +1,35 @@
+mod common;
+use log::error;
+use pretty_assertions::assert_eq;
+use roudoudou::OdooClient;
+use serde_json::json;
+
+#[test]
+fn attachment_upload_download() {
+    common::setup();
+    let mut cli = OdooClient::new();
+    match cli.login("ota3", "admin", "admin") {
+        Err(err) => {
+            error!("could not login to odoo: {}", err);
+        }
+        Ok(cli) => {
+            let model = cli.get_model("ir.attachment").unwrap();
+            let id = model
+                .create(json!({"name": "roudoudou.txt", "type": "binary"}))
+                .unwrap();
+            let attachment = model.browse_lazy(&[id]);
+            let content = b"roudoudou binary test\n".to_vec();
+            assert!(attachment.upload("datas", &content[..]).unwrap());
+
+            let record = attachment.record(0).unwrap();
+            assert!(record.binary_size("datas").is_some());
+            assert_eq!(record.binary("datas").unwrap(), Some(content.clone()));
+
+            let mut downloaded = Vec::new();
+            record.download("datas", &mut downloaded).unwrap();
+            assert_eq!(downloaded, content);
+
+            attachment.unlink().unwrap();
+        }
+    }
+}
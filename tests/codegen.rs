@@ -0,0 +1,22 @@
+//! the checked-in output of `codegen::generate` must compile
+#[path = "generated/models.rs"]
+#[rustfmt::skip]
+mod models;
+
+use models::{ProductProduct, StockLabel, StockLabelState};
+use roudoudou::{OdooModel, Selection};
+
+#[test]
+fn test_generated_models() {
+    assert_eq!(StockLabel::MODEL, "stock.label");
+    assert_eq!(ProductProduct::MODEL, "product.product");
+    let names = StockLabel::field_names();
+    assert!(names.contains(&"self"));
+    assert!(names.contains(&"type"));
+    assert!(!names.contains(&"image"));
+    assert_eq!(
+        StockLabelState::from_key("in_use"),
+        Some(StockLabelState::InUse)
+    );
+    assert_eq!(StockLabelState::Draft.label(), "Draft");
+}
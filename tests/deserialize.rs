@@ -0,0 +1,45 @@
+mod common;
+use log::error;
+use pretty_assertions::assert_eq;
+use roudoudou::serde::{Many2one, OdooOption};
+use roudoudou::OdooClient;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: u32,
+    login: String,
+    partner_id: Many2one,
+    company_ids: Vec<u32>,
+    #[serde(deserialize_with = "roudoudou::serde::false_as_none")]
+    signature: Option<String>,
+    tz: OdooOption<String>,
+}
+
+#[test]
+fn deserialize_users() {
+    common::setup();
+    let mut cli = OdooClient::new();
+    match cli.login("ota3", "admin", "admin") {
+        Err(err) => {
+            error!("could not login to odoo: {}", err);
+        }
+        Ok(cli) => {
+            let admins = cli
+                .get_model("res.users")
+                .unwrap()
+                .search_browse(json!([("login", "=", "admin")]))
+                .unwrap();
+            let users: Vec<User> = admins.deserialize().unwrap();
+            assert_eq!(users.len(), 1);
+            assert_eq!(users[0].id, admins.ids[0]);
+            assert_eq!(users[0].login, "admin");
+            assert!(!users[0].partner_id.name.is_empty());
+            assert!(!users[0].company_ids.is_empty());
+            // `false` is read as `None`, never as an empty string
+            assert!(users[0].signature.iter().all(|text| !text.is_empty()));
+            assert!(users[0].tz.iter().all(|tz| !tz.is_empty()));
+        }
+    }
+}
@@ -0,0 +1,25 @@
+mod common;
+use log::error;
+use pretty_assertions::assert_eq;
+use roudoudou::OdooClient;
+
+#[test]
+fn env_ref_user_root() {
+    common::setup();
+    let mut cli = OdooClient::new();
+    match cli.login("ota3", "admin", "admin") {
+        Err(err) => {
+            error!("could not login to odoo: {}", err);
+        }
+        Ok(cli) => {
+            let admin = cli.env_ref("base.user_root").unwrap();
+            assert_eq!(admin.model.name(), "res.users");
+            assert_eq!(admin.ids, vec![1]);
+
+            let xml_ids = admin.external_ids().unwrap();
+            assert!(xml_ids[0].1.iter().any(|x| x == "base.user_root"));
+
+            assert!(cli.env_ref("base.no_such_record").is_err());
+        }
+    }
+}
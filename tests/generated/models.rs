@@ -0,0 +1,63 @@
+// @generated by roudoudou codegen, do not edit
+#![allow(dead_code, unused_imports, clippy::all)]
+
+use roudoudou::chrono::{DateTime, NaiveDate, Utc};
+use roudoudou::serde_json::Value;
+use roudoudou::{
+    Args, Many2one, OdooClient, OdooModel, OdooSelection, OdooValue, Result, X2many,
+};
+
+/// `product.product`
+#[derive(Debug, Clone, PartialEq, OdooModel)]
+#[odoo(model = "product.product")]
+pub struct ProductProduct {
+    pub id: u32,
+    pub name: Option<String>,
+}
+
+/// `stock.label.state` values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, OdooSelection)]
+pub enum StockLabelState {
+    #[odoo(key = "draft", label = "Draft")]
+    Draft,
+    #[odoo(key = "in_use", label = "In use")]
+    InUse,
+}
+
+/// `stock.label`
+#[derive(Debug, Clone, PartialEq, OdooModel)]
+#[odoo(model = "stock.label")]
+pub struct StockLabel {
+    pub id: u32,
+    #[odoo(readonly)]
+    pub display_name: Option<String>,
+    // image: binary field, not mapped
+    pub location_id: OdooValue,
+    pub name: String,
+    pub product_id: Option<Many2one<ProductProduct>>,
+    #[odoo(rename = "self")]
+    pub self_: Option<String>,
+    /// Status
+    pub state: Option<StockLabelState>,
+    #[odoo(rename = "type")]
+    pub r#type: Option<String>,
+}
+
+impl StockLabel {
+    /// `batch_compute` (Model)
+    pub fn batch_compute(cli: &OdooClient, args: impl Into<Args>, kwargs: Option<Value>) -> Result<Value> {
+        cli.get_model(Self::MODEL)?.call("batch_compute", args, kwargs)
+    }
+    /// `check` (Multi)
+    pub fn check_(cli: &OdooClient, ids: &[u32], args: impl Into<Args>, kwargs: Option<Value>) -> Result<Value> {
+        cli.get_model(Self::MODEL)?.browse_lazy(ids).call("check", args, kwargs)
+    }
+    /// `close` (Multi)
+    pub fn close(cli: &OdooClient, ids: &[u32], args: impl Into<Args>, kwargs: Option<Value>) -> Result<Value> {
+        cli.get_model(Self::MODEL)?.browse_lazy(ids).call("close", args, kwargs)
+    }
+    /// `compute_kind` (One)
+    pub fn compute_kind(&self, cli: &OdooClient, args: impl Into<Args>, kwargs: Option<Value>) -> Result<Value> {
+        cli.get_model(Self::MODEL)?.browse_lazy(&[self.id]).call("compute_kind", args, kwargs)
+    }
+}
@@ -0,0 +1,27 @@
+mod common;
+use log::error;
+use pretty_assertions::assert_eq;
+use roudoudou::OdooClient;
+use serde_json::json;
+
+#[test]
+fn name_search_res_users() {
+    common::setup();
+    let mut cli = OdooClient::new();
+    match cli.login("ota3", "admin", "admin") {
+        Err(err) => {
+            error!("could not login to odoo: {}", err);
+        }
+        Ok(cli) => {
+            let model = cli.get_model("res.users").unwrap();
+            let found = model
+                .name_search("Administrator", json!([]), None, Some(10))
+                .unwrap();
+            assert!(found.iter().any(|(id, _)| *id == 1));
+
+            let users = model.browse(&[1]).unwrap();
+            let names = users.display_names().unwrap();
+            assert_eq!(names, vec![(1, "Administrator".to_owned())]);
+        }
+    }
+}
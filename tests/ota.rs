@@ -52,7 +52,7 @@ macro_rules! assert_attr_eq {
         assert_eq!(($obj).get(stringify!($name)), None)
     };
     ($obj:expr, $name:ident, $expr:tt) => {
-        assert_eq!(($obj).get(stringify!($name)), Some(&json!($expr)))
+        assert_eq!(($obj).get(stringify!($name)), Some(json!($expr)))
     };
 }
 
@@ -87,17 +87,12 @@ fn ota_update_kit_1000_os_version() {
                 }
                 Ok(labels) => {
                     assert_attr_eq!(labels, name, "1000");
-                    match oo_get!(labels, name) {
-                        Some(value) => {
-                            assert_eq!(value, &json!("1000"));
-                        }
-                        None => {
-
-                        }
+                    if let Some(value) = oo_get!(labels, name) {
+                        assert_eq!(value, json!("1000"));
                     }
                     oo_set!(labels, name, "foobar");
 
-                    let name = oo_get!(labels, name);
+                    let _name = oo_get!(labels, name);
 
                     // assert_attr_eq!(labels, os_version, "OPM7.DBLG.012");
                     // assert_attr_eq!(labels, app_version, "1.3.1.9-dblg1-full-commercial");
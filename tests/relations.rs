@@ -0,0 +1,31 @@
+mod common;
+use log::error;
+use pretty_assertions::assert_eq;
+use roudoudou::OdooClient;
+use serde_json::json;
+
+#[test]
+fn stock_label_product_relation() {
+    common::setup();
+    let mut cli = OdooClient::new();
+    match cli.login("ota8", "admin", "admin") {
+        Err(err) => {
+            error!("could not login: {:#?}", err)
+        }
+        Ok(cli) => {
+            let stock_label = cli.get_model("stock.label").unwrap();
+            let labels = stock_label
+                .search_browse(json!([("is_terminal", "=", true), ("id", "<=", 1010)]))
+                .unwrap();
+            let products = labels.related("product_id").unwrap();
+            assert_eq!(products.model.name(), "product.product");
+            assert!(!products.ids.is_empty());
+            assert!(products.get("name").is_some());
+
+            let tags = labels.related("product_tag_ids").unwrap();
+            assert!(tags.ids.iter().all(|id| *id > 0));
+
+            assert!(labels.related("name").is_err());
+        }
+    }
+}
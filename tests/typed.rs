@@ -0,0 +1,51 @@
+mod common;
+use log::error;
+use pretty_assertions::assert_eq;
+use roudoudou::{Many2one, OdooClient, OdooModel};
+use serde_json::json;
+
+#[derive(Debug, OdooModel)]
+#[odoo(model = "res.partner")]
+struct Partner {
+    id: u32,
+    name: String,
+}
+
+#[derive(Debug, OdooModel)]
+#[odoo(model = "res.users")]
+struct User {
+    id: u32,
+    login: String,
+    partner_id: Many2one<Partner>,
+    #[odoo(rename = "active")]
+    enabled: bool,
+}
+
+#[derive(Debug, OdooModel)]
+#[odoo(model = "res.users")]
+struct Broken {
+    #[odoo(rename = "no_such_field")]
+    missing: String,
+}
+
+#[test]
+fn typed_users() {
+    common::setup();
+    let mut cli = OdooClient::new();
+    match cli.login("ota3", "admin", "admin") {
+        Err(err) => {
+            error!("could not login to odoo: {}", err);
+        }
+        Ok(cli) => {
+            let users = cli.typed_model::<User>().unwrap();
+            let admins = users.search(json!([("login", "=", "admin")])).unwrap();
+            assert_eq!(admins.len(), 1);
+            assert_eq!(admins[0].login, "admin");
+            assert!(admins[0].enabled);
+            let partner = admins[0].partner_id.get(cli).unwrap();
+            assert_eq!(partner.id, admins[0].partner_id.id);
+
+            assert!(cli.typed_model::<Broken>().is_err());
+        }
+    }
+}